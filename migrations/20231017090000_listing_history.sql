@@ -0,0 +1,27 @@
+-- Add migration script here
+
+-- Every listings upload is kept here as a snapshot, the listing table only holds the latest one.
+CREATE TABLE listing_history (
+    upload_id UUID NOT NULL,
+    upload_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    world_id INT NOT NULL,
+    item_id INT NOT NULL,
+
+    hq BOOLEAN NOT NULL,
+    seller_id TEXT NOT NULL,
+    retainer_id TEXT NOT NULL,
+    retainer_name TEXT NULL,
+    creator_id TEXT NOT NULL,
+    creator_name TEXT NULL,
+
+    last_review_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    price_per_unit INT NOT NULL,
+    quantity INT NOT NULL,
+
+    retainer_city_id INT NOT NULL,
+    materia_count INT NOT NULL
+);
+
+CREATE INDEX listing_history_item_world_time ON listing_history(item_id, world_id, upload_time);
+CREATE INDEX listing_history_upload_time ON listing_history(upload_time);
+CREATE INDEX upload_item_world_time ON upload(item_id, world_id, upload_time);
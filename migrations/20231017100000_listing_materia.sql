@@ -0,0 +1,19 @@
+-- Add migration script here
+
+-- Position of the listing inside its upload, used to link the materia melds.
+ALTER TABLE listing ADD COLUMN listing_index INT NOT NULL DEFAULT 0;
+ALTER TABLE listing_history ADD COLUMN listing_index INT NOT NULL DEFAULT 0;
+
+CREATE TABLE listing_materia (
+    upload_id UUID NOT NULL,
+    listing_index INT NOT NULL,
+    slot_id INT NOT NULL,
+    materia_id INT NOT NULL
+);
+
+CREATE INDEX listing_materia_listing ON listing_materia(upload_id, listing_index);
+CREATE INDEX listing_materia_materia_id ON listing_materia(materia_id);
+
+-- Needed to find materia rows no longer referenced by any listing.
+CREATE INDEX listing_upload_id ON listing(upload_id);
+CREATE INDEX listing_history_upload_id ON listing_history(upload_id);
@@ -0,0 +1,11 @@
+-- Add migration script here
+
+-- Uploads from the chinese and korean clients are stored under their own region.
+ALTER TABLE upload ADD COLUMN region TEXT NOT NULL DEFAULT 'global';
+ALTER TABLE listing ADD COLUMN region TEXT NOT NULL DEFAULT 'global';
+ALTER TABLE listing_history ADD COLUMN region TEXT NOT NULL DEFAULT 'global';
+ALTER TABLE purchase ADD COLUMN region TEXT NOT NULL DEFAULT 'global';
+
+CREATE INDEX listing_item_region ON listing(item_id, region);
+CREATE INDEX purchase_item_region_time ON purchase(item_id, region, purchase_time);
+CREATE INDEX upload_region_time ON upload(region, upload_time);
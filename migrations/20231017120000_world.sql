@@ -0,0 +1,17 @@
+-- Add migration script here
+
+-- Data centers and worlds, imported from the game data.
+CREATE TABLE data_center (
+    data_center_id INT NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- WorldDCGroupType region: 1 = Japan, 2 = North America, 3 = Europe, 4 = Oceania, 5 = China, 6 = Korea
+    region_id INT NOT NULL
+);
+
+CREATE TABLE world (
+    world_id INT NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    data_center_id INT NOT NULL
+);
+
+CREATE INDEX world_data_center_id ON world(data_center_id);
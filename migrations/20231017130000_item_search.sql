@@ -0,0 +1,7 @@
+-- Add migration script here
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- fuzzy and infix name matches, also used by the ILIKE prefix autocomplete.
+CREATE INDEX item_info_name_trgm ON item_info USING GIN (name gin_trgm_ops);
+CREATE INDEX item_info_description_fts ON item_info USING GIN (to_tsvector('simple', description));
@@ -0,0 +1,22 @@
+-- Add migration script here
+
+-- Translated item texts, item_info keeps the english ones.
+CREATE TABLE item_info_locale (
+    item_id INT NOT NULL,
+    -- en, ja, de or fr
+    lang TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    item_search_category_name TEXT NOT NULL,
+    PRIMARY KEY (item_id, lang)
+);
+
+INSERT INTO item_info_locale (item_id, lang, name, description, item_search_category_name)
+SELECT item_id, 'en', name, description, item_search_category_name FROM item_info;
+
+-- search now goes through the locales.
+DROP INDEX item_info_name_trgm;
+DROP INDEX item_info_description_fts;
+
+CREATE INDEX item_info_locale_name_trgm ON item_info_locale USING GIN (name gin_trgm_ops);
+CREATE INDEX item_info_locale_description_fts ON item_info_locale USING GIN (to_tsvector('simple', description));
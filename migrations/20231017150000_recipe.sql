@@ -0,0 +1,20 @@
+-- Add migration script here
+
+-- Recipes of tradable items, imported from the game data.
+CREATE TABLE recipe (
+    recipe_id INT NOT NULL PRIMARY KEY,
+    -- the crafted item
+    item_id INT NOT NULL,
+    result_quantity INT NOT NULL,
+    -- CraftType: 0 = Carpenter ... 7 = Culinarian
+    craft_type INT NOT NULL
+);
+
+CREATE TABLE recipe_ingredient (
+    recipe_id INT NOT NULL,
+    item_id INT NOT NULL,
+    quantity INT NOT NULL,
+    PRIMARY KEY (recipe_id, item_id)
+);
+
+CREATE INDEX recipe_item_id ON recipe(item_id);
@@ -0,0 +1,6 @@
+-- Add migration script here
+
+-- NPC prices from the Item sheet and whether a GilShop sells the item.
+ALTER TABLE item_info ADD COLUMN price_mid INT NOT NULL DEFAULT 0;
+ALTER TABLE item_info ADD COLUMN price_low INT NOT NULL DEFAULT 0;
+ALTER TABLE item_info ADD COLUMN sold_by_vendor BOOLEAN NOT NULL DEFAULT FALSE;
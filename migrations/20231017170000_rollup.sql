@@ -0,0 +1,39 @@
+-- Add migration script here
+
+-- Purchases per item, world, UTC day and quality, refreshed by the rollup job.
+CREATE TABLE purchase_daily (
+    item_id INT NOT NULL,
+    world_id INT NOT NULL,
+    region TEXT NOT NULL,
+    day DATE NOT NULL,
+    hq BOOLEAN NOT NULL,
+    high INT NOT NULL,
+    low INT NOT NULL,
+    -- sum of the unit prices, the average is price_sum / trades
+    price_sum BIGINT NOT NULL,
+    -- sum of price_per_unit * quantity
+    turnover BIGINT NOT NULL,
+    quantity BIGINT NOT NULL,
+    trades BIGINT NOT NULL,
+    PRIMARY KEY (item_id, world_id, day, hq)
+);
+
+CREATE INDEX purchase_daily_item_region_day ON purchase_daily(item_id, region, day);
+CREATE INDEX purchase_daily_day ON purchase_daily(day);
+
+-- Uploads per UTC day.
+CREATE TABLE upload_daily (
+    day DATE NOT NULL PRIMARY KEY,
+    uploads BIGINT NOT NULL
+);
+
+-- Uploads up to refreshed_until are in the rollups.
+CREATE TABLE rollup_state (
+    name TEXT NOT NULL PRIMARY KEY,
+    refreshed_until TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+INSERT INTO rollup_state (name, refreshed_until) VALUES ('daily', 'epoch');
+
+-- finds the purchases of the history uploads since the last refresh.
+CREATE INDEX purchase_upload_id ON purchase(upload_id);
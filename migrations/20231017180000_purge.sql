@@ -0,0 +1,4 @@
+-- Add migration script here
+
+-- The raw purchases before this day were deleted, the days are only kept in purchase_daily.
+ALTER TABLE rollup_state ADD COLUMN purged_before DATE NOT NULL DEFAULT 'epoch';
@@ -0,0 +1,14 @@
+-- Add migration script here
+
+-- Overlapping history uploads are deduplicated by this key instead of deleting and re-inserting.
+DELETE FROM purchase a USING purchase b
+WHERE a.ctid < b.ctid
+AND a.item_id = b.item_id AND a.world_id = b.world_id AND a.buyer_name = b.buyer_name
+AND a.purchase_time = b.purchase_time AND a.price_per_unit = b.price_per_unit
+AND a.quantity = b.quantity AND a.hq = b.hq;
+
+CREATE UNIQUE INDEX purchase_natural_key
+ON purchase(item_id, world_id, buyer_name, purchase_time, price_per_unit, quantity, hq);
+
+-- the duplicates may be in the rollups, rebuild the days still in purchase.
+UPDATE rollup_state SET refreshed_until = 'epoch';
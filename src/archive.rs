@@ -0,0 +1,147 @@
+//! Purchases deleted by the purge, kept as one zstd compressed JSON Lines file per UTC day.
+//!
+//! A day written by several purges holds one zstd frame per purge, decoders read them as one stream.
+//! The purge appends a frame before it commits the delete and cuts it off again if the commit fails.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+/// A row of the `purchase` table.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchivedPurchase {
+    pub upload_id: Uuid,
+    pub item_id: i32,
+    pub world_id: i32,
+    pub buyer_name: String,
+    pub hq: bool,
+    pub on_mannequin: bool,
+    pub purchase_time: DateTime<Utc>,
+    pub quantity: i32,
+    pub price_per_unit: i32,
+    pub region: String,
+}
+
+/// A frame appended to a day file.
+#[derive(Debug)]
+pub struct Appended {
+    pub path: PathBuf,
+    /// The length of the file before the frame.
+    len: u64,
+}
+
+impl Appended {
+    /// Removes the frame, the file is removed if it only had this frame.
+    pub fn rollback(&self) -> color_eyre::Result<()> {
+        if self.len == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let file = OpenOptions::new().write(true).open(&self.path)?;
+            file.set_len(self.len)?;
+            file.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[must_use]
+pub fn day_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!("purchases-{day}.jsonl.zstd"))
+}
+
+/// Appends the purchases of a day to its file as one frame.
+pub fn append(
+    dir: &Path,
+    day: NaiveDate,
+    purchases: &[ArchivedPurchase],
+) -> color_eyre::Result<Appended> {
+    std::fs::create_dir_all(dir)?;
+
+    let path = day_path(dir, day);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let appended = Appended {
+        len: file.metadata()?.len(),
+        path,
+    };
+
+    let mut enc = zstd::stream::Encoder::new(file, 10)?;
+    for purchase in purchases {
+        serde_json::to_writer(&mut enc, purchase)?;
+        enc.write_all(b"\n")?;
+    }
+    enc.finish()?.sync_all()?;
+
+    Ok(appended)
+}
+
+pub fn read(path: &Path) -> color_eyre::Result<Vec<ArchivedPurchase>> {
+    let dec = zstd::stream::Decoder::new(std::fs::File::open(path)?)?;
+
+    let mut purchases = Vec::new();
+    for line in BufReader::new(dec).lines() {
+        let line = line?;
+        if !line.is_empty() {
+            purchases.push(serde_json::from_str(&line)?);
+        }
+    }
+
+    Ok(purchases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, day_path, read, ArchivedPurchase};
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn append_days() {
+        let dir = std::env::temp_dir().join("xivhub_archive_test");
+        std::fs::remove_dir_all(&dir).ok();
+
+        let purchase = |buyer_name: &str| ArchivedPurchase {
+            upload_id: uuid::Uuid::nil(),
+            item_id: 5,
+            world_id: 73,
+            buyer_name: buyer_name.to_string(),
+            hq: false,
+            on_mannequin: false,
+            purchase_time: Utc.with_ymd_and_hms(2023, 9, 1, 12, 0, 0).unwrap(),
+            quantity: 1,
+            price_per_unit: 100,
+            region: "global".to_string(),
+        };
+        let day = NaiveDate::from_ymd_opt(2023, 9, 1).unwrap();
+        let buyer_names = || {
+            read(&day_path(&dir, day))
+                .unwrap()
+                .into_iter()
+                .map(|x| x.buyer_name)
+                .collect::<Vec<_>>()
+        };
+
+        append(&dir, day, &[purchase("a"), purchase("b")]).unwrap();
+
+        // a later purge adds a frame to the same day.
+        let appended = append(&dir, day, &[purchase("c")]).unwrap();
+        assert_eq!(buyer_names(), ["a", "b", "c"]);
+
+        // and cuts it off again when its delete fails.
+        appended.rollback().unwrap();
+        assert_eq!(buyer_names(), ["a", "b"]);
+
+        let other = NaiveDate::from_ymd_opt(2023, 9, 2).unwrap();
+        append(&dir, other, &[purchase("d")])
+            .unwrap()
+            .rollback()
+            .unwrap();
+        assert!(!day_path(&dir, other).exists());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,54 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(clippy::missing_const_for_fn)]
+#![deny(clippy::nursery)]
+#![deny(clippy::pedantic)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::missing_panics_doc)]
+
+use color_eyre::eyre::bail;
+use std::path::Path;
+use xivhub_market::{bundle, diff::ItemDiff, entities::ItemInfo};
+
+// Tool to compare two item bundles, e.g. before and after a game patch.
+// Usage: diff [--json] <old items.bin.zstd> <new items.bin.zstd>
+fn main() -> color_eyre::Result<()> {
+    let mut json = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        if arg == "--json" {
+            json = true;
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    let [old, new] = paths.as_slice() else {
+        bail!("usage: diff [--json] <old items.bin.zstd> <new items.bin.zstd>");
+    };
+
+    let (old_header, old): (_, Vec<ItemInfo>) = bundle::read(Path::new(old))?;
+    let (new_header, new): (_, Vec<ItemInfo>) = bundle::read(Path::new(new))?;
+
+    let diff = ItemDiff::new(&old, &new);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "old": old_header,
+                "new": new_header,
+                "diff": diff,
+            }))?
+        );
+    } else {
+        println!(
+            "game version {} -> {}",
+            old_header.game_version, new_header.game_version
+        );
+        print!("{diff}");
+    }
+
+    Ok(())
+}
@@ -9,9 +9,16 @@
 
 use ironworks::{excel::Excel, ffxiv, sqpack::SqPack, Ironworks};
 use ironworks_sheets::{for_type, sheet};
-use std::{io::Write, time::Instant};
-use tracing::info;
-use xivhub_market::entities::ItemInfo;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use tracing::{info, warn};
+use xivhub_market::{
+    bundle::{self, Bundle},
+    entities::{DataCenter, ItemInfo, ItemLocale, Recipe, RecipeIngredient, World, WorldBundle},
+};
 
 // Tool to import game data and store it in a better format. Requires the game to be installed.
 fn main() -> color_eyre::Result<()> {
@@ -24,18 +31,72 @@ fn main() -> color_eyre::Result<()> {
     // initialize tracing
     tracing_subscriber::fmt::init();
 
-    let ironworks =
-        Ironworks::new().with_resource(SqPack::new(ffxiv::FsResource::search().unwrap()));
+    // `FsResource::search` does not tell where it found the game, only a given install has a version.
+    let install = std::env::var("XIVHUB_GAME_PATH").ok().map(PathBuf::from);
+    let resource = match &install {
+        Some(install) => ffxiv::FsResource::at(install),
+        None => ffxiv::FsResource::search().expect("game install, set XIVHUB_GAME_PATH"),
+    };
+    let ironworks = Ironworks::new().with_resource(SqPack::new(resource));
 
     // Read fields out of excel.
     let excel = Excel::with()
         .language(ffxiv::Language::English)
         .build(&ironworks, ffxiv::Mapper::new());
 
+    let items = extract_items(&excel)?;
+    let locales = extract_locales(&ironworks, &items)?;
+    let worlds = extract_worlds(&excel)?;
+    let recipes = extract_recipes(&excel, &items)?;
+
+    // stored in the bundle headers, the env var overrides the version of the install.
+    let game_version = match (std::env::var("XIVHUB_GAME_VERSION"), &install) {
+        (Ok(version), _) => version,
+        (Err(_), Some(install)) => read_game_version(install)?,
+        (Err(_), None) => {
+            warn!("Unknown game version, set XIVHUB_GAME_PATH or XIVHUB_GAME_VERSION");
+            String::from("unknown")
+        }
+    };
+    info!("Extracting game version {game_version}");
+
+    std::fs::create_dir("assets").ok();
+    write_bundle(
+        std::path::Path::new("assets/items.bin.zstd"),
+        &items,
+        &game_version,
+    )?;
+    write_bundle(
+        std::path::Path::new("assets/item_locales.bin.zstd"),
+        &locales,
+        &game_version,
+    )?;
+    write_bundle(
+        std::path::Path::new("assets/worlds.bin.zstd"),
+        &worlds,
+        &game_version,
+    )?;
+    write_bundle(
+        std::path::Path::new("assets/recipes.bin.zstd"),
+        &recipes,
+        &game_version,
+    )?;
+
+    Ok(())
+}
+
+fn extract_items(excel: &Excel) -> color_eyre::Result<Vec<ItemInfo>> {
     let items_sheet = excel.sheet(for_type::<sheet::Item>())?;
     let item_search_category_sheet = excel.sheet(for_type::<sheet::ItemSearchCategory>())?;
     let item_ui_category_sheet = excel.sheet(for_type::<sheet::ItemUICategory>())?;
 
+    // GilShopItem has a subrow per item sold in each gil shop.
+    let gil_shop_items: HashSet<u32> = excel
+        .sheet(for_type::<sheet::GilShopItem>())?
+        .iter()
+        .filter_map(|x| u32::try_from(x.item).ok())
+        .collect();
+
     // I expect there to be about 15k items (after filtering)
     let mut items: Vec<ItemInfo> = Vec::with_capacity(15000);
 
@@ -75,6 +136,9 @@ fn main() -> color_eyre::Result<()> {
                     materia_slot_count: item.materia_slot_count.into(),
                     rarity: item.rarity.into(),
                     can_be_hq: item.can_be_hq,
+                    price_mid: item.price_mid.try_into().unwrap(),
+                    price_low: item.price_low.try_into().unwrap(),
+                    sold_by_vendor: gil_shop_items.contains(&id),
                 });
             }
         } else {
@@ -85,14 +149,163 @@ fn main() -> color_eyre::Result<()> {
     let elapsed = now.elapsed();
     info!("Got {} items in {elapsed:?}", items.len());
 
-    std::fs::create_dir("assets").ok();
-    let output = std::path::Path::new("assets/items.bin.zstd");
-    let file = std::fs::File::create(output)?;
-    let mut enc = zstd::stream::Encoder::new(file, 10)?;
+    Ok(items)
+}
+
+/// Texts of `items` in every client language.
+fn extract_locales(
+    ironworks: &Ironworks,
+    items: &[ItemInfo],
+) -> color_eyre::Result<Vec<ItemLocale>> {
+    let now = Instant::now();
+    let mut locales: Vec<ItemLocale> = Vec::with_capacity(items.len() * 4);
+
+    for (language, lang) in [
+        (ffxiv::Language::English, "en"),
+        (ffxiv::Language::Japanese, "ja"),
+        (ffxiv::Language::German, "de"),
+        (ffxiv::Language::French, "fr"),
+    ] {
+        let excel = Excel::with()
+            .language(language)
+            .build(ironworks, ffxiv::Mapper::new());
+        let items_sheet = excel.sheet(for_type::<sheet::Item>())?;
+        let item_search_category_sheet = excel.sheet(for_type::<sheet::ItemSearchCategory>())?;
+
+        for item in items {
+            let row = items_sheet.row(item.item_id.try_into().unwrap())?;
+            let name = row.name.to_string();
+
+            // the api falls back to english for missing translations.
+            if name.is_empty() {
+                continue;
+            }
+
+            let search_category =
+                item_search_category_sheet.row(item.item_search_category.try_into().unwrap())?;
+
+            locales.push(ItemLocale {
+                item_id: item.item_id,
+                lang: lang.to_string(),
+                name,
+                description: row.description.to_string(),
+                item_search_category_name: search_category.name.to_string(),
+            });
+        }
+    }
+
+    let elapsed = now.elapsed();
+    info!("Got {} item translations in {elapsed:?}", locales.len());
+
+    Ok(locales)
+}
+
+fn extract_worlds(excel: &Excel) -> color_eyre::Result<WorldBundle> {
+    let world_sheet = excel.sheet(for_type::<sheet::World>())?;
+    let data_center_sheet = excel.sheet(for_type::<sheet::WorldDCGroupType>())?;
+
+    let mut worlds = WorldBundle::default();
+
+    for id in 0.. {
+        if let Ok(dc) = data_center_sheet.row(id) {
+            let name = dc.name.to_string();
+            // 0 is the placeholder row, 7 is the cloud test data center.
+            if !name.is_empty() && (1..=6).contains(&dc.region) {
+                worlds.data_centers.push(DataCenter {
+                    data_center_id: id.try_into().unwrap(),
+                    name,
+                    region_id: dc.region.into(),
+                });
+            }
+        } else {
+            break;
+        }
+    }
+
+    for id in 0.. {
+        if let Ok(world) = world_sheet.row(id) {
+            let data_center_id: i32 = world.data_center.into();
+            if world.is_public
+                && worlds
+                    .data_centers
+                    .iter()
+                    .any(|dc| dc.data_center_id == data_center_id)
+            {
+                worlds.worlds.push(World {
+                    world_id: id.try_into().unwrap(),
+                    name: world.name.to_string(),
+                    data_center_id,
+                });
+            }
+        } else {
+            break;
+        }
+    }
+
+    info!(
+        "Got {} worlds in {} data centers",
+        worlds.worlds.len(),
+        worlds.data_centers.len()
+    );
+
+    Ok(worlds)
+}
+
+/// Recipes crafting one of `items`.
+fn extract_recipes(excel: &Excel, items: &[ItemInfo]) -> color_eyre::Result<Vec<Recipe>> {
+    let recipe_sheet = excel.sheet(for_type::<sheet::Recipe>())?;
+    let item_ids: HashSet<i32> = items.iter().map(|x| x.item_id).collect();
+
+    let mut recipes = Vec::new();
+
+    for id in 0.. {
+        if let Ok(recipe) = recipe_sheet.row(id) {
+            let item_id: i32 = recipe.item_result.try_into().unwrap();
+            if !item_ids.contains(&item_id) {
+                continue;
+            }
+
+            // unused ingredient slots have no item or no amount.
+            let ingredients = recipe
+                .item_ingredient
+                .iter()
+                .zip(recipe.amount_ingredient.iter())
+                .filter(|(item, amount)| **item > 0 && **amount > 0)
+                .map(|(item, amount)| RecipeIngredient {
+                    item_id: (*item).try_into().unwrap(),
+                    quantity: (*amount).into(),
+                })
+                .collect();
+
+            recipes.push(Recipe {
+                recipe_id: id.try_into().unwrap(),
+                item_id,
+                result_quantity: recipe.amount_result.into(),
+                craft_type: recipe.craft_type.try_into().unwrap(),
+                ingredients,
+            });
+        } else {
+            break;
+        }
+    }
+
+    info!("Got {} recipes", recipes.len());
+
+    Ok(recipes)
+}
+
+/// The content of `game/ffxivgame.ver`, e.g. `2023.10.03.0000.0000`.
+fn read_game_version(install: &Path) -> color_eyre::Result<String> {
+    let version = std::fs::read_to_string(install.join("game").join("ffxivgame.ver"))?;
+    Ok(version.trim().to_string())
+}
 
-    bincode::serialize_into(&mut enc, &items)?;
-    enc.flush()?;
-    enc.finish()?;
+fn write_bundle<T: Bundle>(
+    output: &std::path::Path,
+    value: &T,
+    game_version: &str,
+) -> color_eyre::Result<()> {
+    bundle::write(output, value, game_version)?;
 
     info!("Saved to {output:?}");
 
@@ -10,9 +10,13 @@
 
 use std::time::Instant;
 
-use sqlx::postgres::PgPoolOptions;
-use tracing::info;
-use xivhub_market::entities::ItemInfo;
+use sqlx::{postgres::PgPoolOptions, Postgres, Transaction};
+use tracing::{info, warn};
+use xivhub_market::{
+    bundle::{self, Header},
+    diff::ItemDiff,
+    entities::{ItemInfo, ItemLocale, Recipe, WorldBundle},
+};
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
@@ -29,11 +33,12 @@ async fn run() -> color_eyre::Result<()> {
     // initialize tracing
     tracing_subscriber::fmt::init();
 
-    let input = std::path::Path::new("assets/items.bin.zstd");
-    let file = std::fs::File::open(input)?;
-    let mut decoder = zstd::stream::Decoder::new(file)?;
+    // only prints what would change.
+    let dry_run = std::env::args().skip(1).any(|x| x == "--dry-run");
 
-    let items: Vec<ItemInfo> = bincode::deserialize_from(&mut decoder)?;
+    let input = std::path::Path::new("assets/items.bin.zstd");
+    let (header, items): (_, Vec<ItemInfo>) = bundle::read(input)?;
+    log_header(&header);
 
     info!("Loaded {} items from {input:?}", items.len());
 
@@ -49,39 +54,24 @@ async fn run() -> color_eyre::Result<()> {
     let start = Instant::now();
     let mut tx = pool.begin().await?;
 
-    sqlx::query!("DELETE FROM item_info")
-        .execute(&mut tx)
+    let current = sqlx::query_as!(ItemInfo, "SELECT * FROM item_info")
+        .fetch_all(&mut tx)
         .await?;
+    let diff = ItemDiff::new(&current, &items);
+    print!("{diff}");
 
-    for item in items {
-        sqlx::query!(
-            "INSERT INTO item_info
-            (item_id, name, icon, icon_hd, description, item_kind_name, item_kind_id, item_search_category,
-            item_search_category_iconhd, item_search_category_name,
-            stack_size, level_item, level_equip, materia_slot_count, rarity, can_be_hq)
-            VALUES
-            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-            ",
-            item.item_id,
-            item.name,
-            item.icon,
-            item.icon_hd,
-            item.description,
-            item.item_kind_name,
-            item.item_kind_id,
-            item.item_search_category,
-            item.item_search_category_iconhd,
-            item.item_search_category_name,
-            item.stack_size,
-            item.level_item,
-            item.level_equip,
-            item.materia_slot_count,
-            item.rarity,
-            item.can_be_hq
-        )
-        .execute(&mut tx).await?;
+    if dry_run {
+        info!("Dry run, nothing was written");
+        return Ok(());
     }
 
+    let rows = upsert_items(&mut tx, &items).await?;
+    info!("Wrote {rows} items");
+
+    import_locales(&mut tx, &items).await?;
+    import_worlds(&mut tx).await?;
+    import_recipes(&mut tx).await?;
+
     tx.commit().await?;
 
     let elapsed = start.elapsed();
@@ -89,3 +79,287 @@ async fn run() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+/// Inserts new items, updates changed ones and deletes the ones missing from `items`.
+async fn upsert_items(
+    tx: &mut Transaction<'_, Postgres>,
+    items: &[ItemInfo],
+) -> color_eyre::Result<u64> {
+    let mut item_ids = Vec::with_capacity(items.len());
+    let mut names = Vec::with_capacity(items.len());
+    let mut icons = Vec::with_capacity(items.len());
+    let mut icons_hd = Vec::with_capacity(items.len());
+    let mut descriptions = Vec::with_capacity(items.len());
+    let mut kind_names = Vec::with_capacity(items.len());
+    let mut kind_ids = Vec::with_capacity(items.len());
+    let mut categories = Vec::with_capacity(items.len());
+    let mut category_icons = Vec::with_capacity(items.len());
+    let mut category_names = Vec::with_capacity(items.len());
+    let mut stack_sizes = Vec::with_capacity(items.len());
+    let mut levels_item = Vec::with_capacity(items.len());
+    let mut levels_equip = Vec::with_capacity(items.len());
+    let mut materia_slots = Vec::with_capacity(items.len());
+    let mut rarities = Vec::with_capacity(items.len());
+    let mut can_be_hq = Vec::with_capacity(items.len());
+    let mut prices_mid = Vec::with_capacity(items.len());
+    let mut prices_low = Vec::with_capacity(items.len());
+    let mut sold_by_vendor = Vec::with_capacity(items.len());
+    for item in items {
+        item_ids.push(item.item_id);
+        names.push(item.name.clone());
+        icons.push(item.icon.clone());
+        icons_hd.push(item.icon_hd.clone());
+        descriptions.push(item.description.clone());
+        kind_names.push(item.item_kind_name.clone());
+        kind_ids.push(item.item_kind_id);
+        categories.push(item.item_search_category);
+        category_icons.push(item.item_search_category_iconhd.clone());
+        category_names.push(item.item_search_category_name.clone());
+        stack_sizes.push(item.stack_size);
+        levels_item.push(item.level_item);
+        levels_equip.push(item.level_equip);
+        materia_slots.push(item.materia_slot_count);
+        rarities.push(item.rarity);
+        can_be_hq.push(item.can_be_hq);
+        prices_mid.push(item.price_mid);
+        prices_low.push(item.price_low);
+        sold_by_vendor.push(item.sold_by_vendor);
+    }
+
+    // unchanged rows are skipped so they are not rewritten.
+    let written = sqlx::query!(
+        "INSERT INTO item_info
+        (item_id, name, icon, icon_hd, description, item_kind_name, item_kind_id, item_search_category,
+        item_search_category_iconhd, item_search_category_name,
+        stack_size, level_item, level_equip, materia_slot_count, rarity, can_be_hq,
+        price_mid, price_low, sold_by_vendor)
+        SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[],
+            $7::INT[], $8::INT[], $9::TEXT[], $10::TEXT[], $11::INT[], $12::INT[], $13::INT[],
+            $14::INT[], $15::INT[], $16::BOOL[], $17::INT[], $18::INT[], $19::BOOL[])
+        ON CONFLICT (item_id) DO UPDATE SET
+            name = EXCLUDED.name, icon = EXCLUDED.icon, icon_hd = EXCLUDED.icon_hd,
+            description = EXCLUDED.description, item_kind_name = EXCLUDED.item_kind_name,
+            item_kind_id = EXCLUDED.item_kind_id, item_search_category = EXCLUDED.item_search_category,
+            item_search_category_iconhd = EXCLUDED.item_search_category_iconhd,
+            item_search_category_name = EXCLUDED.item_search_category_name,
+            stack_size = EXCLUDED.stack_size, level_item = EXCLUDED.level_item,
+            level_equip = EXCLUDED.level_equip, materia_slot_count = EXCLUDED.materia_slot_count,
+            rarity = EXCLUDED.rarity, can_be_hq = EXCLUDED.can_be_hq,
+            price_mid = EXCLUDED.price_mid, price_low = EXCLUDED.price_low,
+            sold_by_vendor = EXCLUDED.sold_by_vendor
+        WHERE item_info IS DISTINCT FROM EXCLUDED",
+        &item_ids,
+        &names,
+        &icons,
+        &icons_hd,
+        &descriptions,
+        &kind_names,
+        &kind_ids,
+        &categories,
+        &category_icons,
+        &category_names,
+        &stack_sizes,
+        &levels_item,
+        &levels_equip,
+        &materia_slots,
+        &rarities,
+        &can_be_hq,
+        &prices_mid,
+        &prices_low,
+        &sold_by_vendor
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let removed = sqlx::query!("DELETE FROM item_info WHERE item_id <> ALL($1)", &item_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    Ok(written + removed)
+}
+
+/// Upserts the item translations, or only the english texts for older asset folders.
+/// Only the languages present in the bundle are cleaned up.
+async fn import_locales(
+    tx: &mut Transaction<'_, Postgres>,
+    items: &[ItemInfo],
+) -> color_eyre::Result<()> {
+    let input = std::path::Path::new("assets/item_locales.bin.zstd");
+    let locales: Vec<ItemLocale> = if input.exists() {
+        let (header, locales): (_, Vec<ItemLocale>) = bundle::read(input)?;
+        log_header(&header);
+        info!("Loaded {} item translations from {input:?}", locales.len());
+        locales
+    } else {
+        warn!("{input:?} not found, only importing english texts");
+        items
+            .iter()
+            .map(|item| ItemLocale {
+                item_id: item.item_id,
+                lang: "en".to_string(),
+                name: item.name.clone(),
+                description: item.description.clone(),
+                item_search_category_name: item.item_search_category_name.clone(),
+            })
+            .collect()
+    };
+
+    let mut item_ids = Vec::with_capacity(locales.len());
+    let mut langs = Vec::with_capacity(locales.len());
+    let mut names = Vec::with_capacity(locales.len());
+    let mut descriptions = Vec::with_capacity(locales.len());
+    let mut category_names = Vec::with_capacity(locales.len());
+    for locale in locales {
+        item_ids.push(locale.item_id);
+        langs.push(locale.lang);
+        names.push(locale.name);
+        descriptions.push(locale.description);
+        category_names.push(locale.item_search_category_name);
+    }
+
+    let written = sqlx::query!(
+        "INSERT INTO item_info_locale (item_id, lang, name, description, item_search_category_name)
+        SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[])
+        ON CONFLICT (item_id, lang) DO UPDATE SET
+            name = EXCLUDED.name, description = EXCLUDED.description,
+            item_search_category_name = EXCLUDED.item_search_category_name
+        WHERE item_info_locale IS DISTINCT FROM EXCLUDED",
+        &item_ids,
+        &langs,
+        &names,
+        &descriptions,
+        &category_names
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let removed = sqlx::query!(
+        "DELETE FROM item_info_locale
+        WHERE lang = ANY($2) AND (item_id, lang) NOT IN (SELECT * FROM UNNEST($1::INT[], $2::TEXT[]))",
+        &item_ids,
+        &langs
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    info!("Wrote {written} and removed {removed} item translations");
+
+    Ok(())
+}
+
+async fn import_worlds(tx: &mut Transaction<'_, Postgres>) -> color_eyre::Result<()> {
+    // Older asset folders may not have the worlds bundle yet.
+    let input = std::path::Path::new("assets/worlds.bin.zstd");
+    if input.exists() {
+        let (header, worlds): (_, WorldBundle) = bundle::read(input)?;
+        log_header(&header);
+
+        info!(
+            "Loaded {} worlds and {} data centers from {input:?}",
+            worlds.worlds.len(),
+            worlds.data_centers.len()
+        );
+
+        sqlx::query!("DELETE FROM world").execute(&mut *tx).await?;
+        sqlx::query!("DELETE FROM data_center")
+            .execute(&mut *tx)
+            .await?;
+
+        for dc in worlds.data_centers {
+            sqlx::query!(
+                "INSERT INTO data_center (data_center_id, name, region_id) VALUES ($1, $2, $3)",
+                dc.data_center_id,
+                dc.name,
+                dc.region_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for world in worlds.worlds {
+            sqlx::query!(
+                "INSERT INTO world (world_id, name, data_center_id) VALUES ($1, $2, $3)",
+                world.world_id,
+                world.name,
+                world.data_center_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    } else {
+        warn!("{input:?} not found, skipping worlds");
+    }
+
+    Ok(())
+}
+
+async fn import_recipes(tx: &mut Transaction<'_, Postgres>) -> color_eyre::Result<()> {
+    // Older asset folders may not have the recipes bundle yet.
+    let input = std::path::Path::new("assets/recipes.bin.zstd");
+    if !input.exists() {
+        warn!("{input:?} not found, skipping recipes");
+        return Ok(());
+    }
+
+    let (header, recipes): (_, Vec<Recipe>) = bundle::read(input)?;
+    log_header(&header);
+    info!("Loaded {} recipes from {input:?}", recipes.len());
+
+    let mut recipe_ids = Vec::with_capacity(recipes.len());
+    let mut item_ids = Vec::with_capacity(recipes.len());
+    let mut result_quantities = Vec::with_capacity(recipes.len());
+    let mut craft_types = Vec::with_capacity(recipes.len());
+    let mut ingredient_recipe_ids = Vec::new();
+    let mut ingredient_item_ids = Vec::new();
+    let mut ingredient_quantities = Vec::new();
+    for recipe in recipes {
+        recipe_ids.push(recipe.recipe_id);
+        item_ids.push(recipe.item_id);
+        result_quantities.push(recipe.result_quantity);
+        craft_types.push(recipe.craft_type);
+        for ingredient in recipe.ingredients {
+            ingredient_recipe_ids.push(recipe.recipe_id);
+            ingredient_item_ids.push(ingredient.item_id);
+            ingredient_quantities.push(ingredient.quantity);
+        }
+    }
+
+    sqlx::query!("DELETE FROM recipe_ingredient")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM recipe").execute(&mut *tx).await?;
+
+    sqlx::query!(
+        "INSERT INTO recipe (recipe_id, item_id, result_quantity, craft_type)
+        SELECT * FROM UNNEST($1::INT[], $2::INT[], $3::INT[], $4::INT[])",
+        &recipe_ids,
+        &item_ids,
+        &result_quantities,
+        &craft_types
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO recipe_ingredient (recipe_id, item_id, quantity)
+        SELECT * FROM UNNEST($1::INT[], $2::INT[], $3::INT[])",
+        &ingredient_recipe_ids,
+        &ingredient_item_ids,
+        &ingredient_quantities
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+fn log_header(header: &Header) {
+    info!(
+        "{} bundle: schema version {}, game version {}, extracted at {}",
+        header.kind, header.schema_version, header.game_version, header.extracted_at
+    );
+}
@@ -0,0 +1,178 @@
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+#![deny(clippy::missing_const_for_fn)]
+#![deny(clippy::nursery)]
+#![deny(clippy::pedantic)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::missing_panics_doc)]
+
+use chrono::NaiveDate;
+use color_eyre::eyre::bail;
+use sqlx::postgres::PgPoolOptions;
+use std::{path::PathBuf, time::Instant};
+use tracing::{info, warn};
+use xivhub_market::archive::{self, ArchivedPurchase};
+
+/// Rows per insert.
+const CHUNK_SIZE: usize = 10_000;
+
+// Tool to load archived purchases into the purchase_archive table of a scratch database, created if missing.
+// Usage: unarchive --database-url <url> [--dir <archive dir>] <from day> <to day>
+// The url is required so the tool never picks up the api's DATABASE_URL from .env.
+// The dir defaults to XIVHUB_ARCHIVE_DIR, the days are inclusive and replace what the table had for them.
+fn main() -> color_eyre::Result<()> {
+    let mut database_url = None;
+    let mut dir = std::env::var("XIVHUB_ARCHIVE_DIR").ok().map(PathBuf::from);
+    let mut days = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--database-url" {
+            database_url = args.next();
+        } else if arg == "--dir" {
+            dir = args.next().map(PathBuf::from);
+        } else {
+            days.push(arg.parse::<NaiveDate>()?);
+        }
+    }
+
+    let (Some(database_url), Some(dir), &[from, to]) = (database_url, dir, days.as_slice()) else {
+        bail!("usage: unarchive --database-url <url> [--dir <archive dir>] <from day> <to day>");
+    };
+    if from > to {
+        bail!("{from} is after {to}");
+    }
+
+    tokio::runtime::Runtime::new()?.block_on(run(&database_url, dir, from, to))
+}
+
+async fn run(
+    database_url: &str,
+    dir: PathBuf,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> color_eyre::Result<()> {
+    std::env::set_var(
+        "RUST_LOG",
+        std::env::var("RUST_LOG").unwrap_or_else(|_| String::from("info")),
+    );
+
+    // initialize tracing
+    tracing_subscriber::fmt::init();
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await?;
+
+    // the table is not part of the migrations, the queries on it are not checked at compile time.
+    // the natural key drops the rows a purge archived twice.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS purchase_archive (
+            upload_id UUID NOT NULL,
+            item_id INT NOT NULL,
+            world_id INT NOT NULL,
+            buyer_name TEXT NOT NULL,
+            hq BOOLEAN NOT NULL,
+            on_mannequin BOOLEAN NOT NULL,
+            purchase_time TIMESTAMP WITH TIME ZONE NOT NULL,
+            quantity INT NOT NULL,
+            price_per_unit INT NOT NULL,
+            region TEXT NOT NULL
+        )",
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS purchase_archive_natural_key ON purchase_archive
+        (item_id, world_id, buyer_name, purchase_time, price_per_unit, quantity, hq)",
+    )
+    .execute(&pool)
+    .await?;
+
+    let start = Instant::now();
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query(
+        "DELETE FROM purchase_archive
+        WHERE purchase_time >= $1::DATE::TIMESTAMP AT TIME ZONE 'UTC'
+        AND purchase_time < ($2::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC'",
+    )
+    .bind(from)
+    .bind(to)
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+    info!("Deleted {deleted} purchases already loaded for the range");
+
+    let mut loaded = 0;
+    for day in from.iter_days().take_while(|x| *x <= to) {
+        let path = archive::day_path(&dir, day);
+        if !path.exists() {
+            warn!("{} not found, skipping {day}", path.display());
+            continue;
+        }
+
+        let purchases = archive::read(&path)?;
+        for chunk in purchases.chunks(CHUNK_SIZE) {
+            insert(&mut tx, chunk).await?;
+        }
+        loaded += purchases.len();
+    }
+
+    tx.commit().await?;
+
+    info!("Loaded {loaded} purchases in {:?}", start.elapsed());
+
+    Ok(())
+}
+
+async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    purchases: &[ArchivedPurchase],
+) -> color_eyre::Result<()> {
+    let mut upload_ids = Vec::with_capacity(purchases.len());
+    let mut item_ids = Vec::with_capacity(purchases.len());
+    let mut world_ids = Vec::with_capacity(purchases.len());
+    let mut buyer_names = Vec::with_capacity(purchases.len());
+    let mut hqs = Vec::with_capacity(purchases.len());
+    let mut on_mannequins = Vec::with_capacity(purchases.len());
+    let mut purchase_times = Vec::with_capacity(purchases.len());
+    let mut quantities = Vec::with_capacity(purchases.len());
+    let mut prices = Vec::with_capacity(purchases.len());
+    let mut regions = Vec::with_capacity(purchases.len());
+    for purchase in purchases {
+        upload_ids.push(purchase.upload_id);
+        item_ids.push(purchase.item_id);
+        world_ids.push(purchase.world_id);
+        buyer_names.push(purchase.buyer_name.clone());
+        hqs.push(purchase.hq);
+        on_mannequins.push(purchase.on_mannequin);
+        purchase_times.push(purchase.purchase_time);
+        quantities.push(purchase.quantity);
+        prices.push(purchase.price_per_unit);
+        regions.push(purchase.region.clone());
+    }
+
+    sqlx::query(
+        "INSERT INTO purchase_archive
+        (upload_id, item_id, world_id, buyer_name, hq, on_mannequin, purchase_time, quantity, price_per_unit, region)
+        SELECT * FROM UNNEST($1::UUID[], $2::INT[], $3::INT[], $4::TEXT[], $5::BOOL[], $6::BOOL[],
+            $7::TIMESTAMPTZ[], $8::INT[], $9::INT[], $10::TEXT[])
+        ON CONFLICT DO NOTHING",
+    )
+    .bind(&upload_ids)
+    .bind(&item_ids)
+    .bind(&world_ids)
+    .bind(&buyer_names)
+    .bind(&hqs)
+    .bind(&on_mannequins)
+    .bind(&purchase_times)
+    .bind(&quantities)
+    .bind(&prices)
+    .bind(&regions)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
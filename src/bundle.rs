@@ -0,0 +1,234 @@
+//! The files in `assets/` written by `bin/extract`.
+//!
+//! A bundle is `MAGIC` followed by a zstd stream holding a bincode `Header` and the bincode payload.
+//! Files without `MAGIC` are the headerless bundles of schema version 1.
+
+use crate::entities::{ItemInfo, ItemLocale, Recipe, WorldBundle};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, WrapErr};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{io::Write, path::Path};
+
+pub const MAGIC: &[u8; 8] = b"XIVHUBB\0";
+
+/// Bump when an entity stored in a bundle changes, and handle the old version in `Bundle::migrate`.
+pub const SCHEMA_VERSION: u32 = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Header {
+    /// `Bundle::KIND` of the payload.
+    pub kind: String,
+    pub schema_version: u32,
+    /// The game version the data was extracted from.
+    pub game_version: String,
+    pub extracted_at: DateTime<Utc>,
+    /// sha256 of the payload.
+    pub checksum: String,
+}
+
+pub trait Bundle: Serialize + DeserializeOwned {
+    const KIND: &'static str;
+
+    /// Reads the payload of an older schema version.
+    fn migrate(schema_version: u32, payload: &[u8]) -> color_eyre::Result<Self> {
+        match schema_version {
+            // 2 added the header, 3 only changed items.
+            1 | 2 => Ok(bincode::deserialize(payload)?),
+            _ => bail!(
+                "no migration from schema version {schema_version} for {} bundles",
+                Self::KIND
+            ),
+        }
+    }
+}
+
+impl Bundle for Vec<ItemInfo> {
+    const KIND: &'static str = "items";
+
+    fn migrate(schema_version: u32, payload: &[u8]) -> color_eyre::Result<Self> {
+        match schema_version {
+            // the NPC prices were added in 3, they are unknown before.
+            1 | 2 => {
+                let items: Vec<ItemInfoV2> = bincode::deserialize(payload)?;
+                Ok(items.into_iter().map(ItemInfo::from).collect())
+            }
+            _ => bail!("no migration from schema version {schema_version} for items bundles"),
+        }
+    }
+}
+
+/// `ItemInfo` up to schema version 2.
+#[derive(Deserialize)]
+struct ItemInfoV2 {
+    item_id: i32,
+    name: String,
+    icon: String,
+    icon_hd: String,
+    description: String,
+    item_kind_name: String,
+    item_kind_id: i32,
+    item_search_category: i32,
+    item_search_category_iconhd: String,
+    item_search_category_name: String,
+    stack_size: i32,
+    level_item: i32,
+    level_equip: i32,
+    materia_slot_count: i32,
+    rarity: i32,
+    can_be_hq: bool,
+}
+
+impl From<ItemInfoV2> for ItemInfo {
+    fn from(x: ItemInfoV2) -> Self {
+        Self {
+            item_id: x.item_id,
+            name: x.name,
+            icon: x.icon,
+            icon_hd: x.icon_hd,
+            description: x.description,
+            item_kind_name: x.item_kind_name,
+            item_kind_id: x.item_kind_id,
+            item_search_category: x.item_search_category,
+            item_search_category_iconhd: x.item_search_category_iconhd,
+            item_search_category_name: x.item_search_category_name,
+            stack_size: x.stack_size,
+            level_item: x.level_item,
+            level_equip: x.level_equip,
+            materia_slot_count: x.materia_slot_count,
+            rarity: x.rarity,
+            can_be_hq: x.can_be_hq,
+            price_mid: 0,
+            price_low: 0,
+            sold_by_vendor: false,
+        }
+    }
+}
+
+impl Bundle for Vec<ItemLocale> {
+    const KIND: &'static str = "item_locales";
+}
+
+impl Bundle for WorldBundle {
+    const KIND: &'static str = "worlds";
+}
+
+impl Bundle for Vec<Recipe> {
+    const KIND: &'static str = "recipes";
+}
+
+/// Reads a bundle, migrating older schema versions and rejecting newer or corrupted ones.
+pub fn read<T: Bundle>(input: &Path) -> color_eyre::Result<(Header, T)> {
+    decode(input).wrap_err_with(|| format!("invalid bundle {}", input.display()))
+}
+
+fn decode<T: Bundle>(input: &Path) -> color_eyre::Result<(Header, T)> {
+    let file = std::fs::read(input)?;
+
+    let Some(compressed) = file.strip_prefix(MAGIC) else {
+        let payload = zstd::stream::decode_all(file.as_slice())?;
+        let header = Header {
+            kind: T::KIND.to_string(),
+            schema_version: 1,
+            game_version: "unknown".to_string(),
+            extracted_at: std::fs::metadata(input)?.modified()?.into(),
+            checksum: sha256::digest(payload.as_slice()),
+        };
+        let value = T::migrate(1, &payload)?;
+        return Ok((header, value));
+    };
+
+    let decompressed = zstd::stream::decode_all(compressed)?;
+    let mut reader = decompressed.as_slice();
+    let header: Header = bincode::deserialize_from(&mut reader)?;
+    let payload: Vec<u8> = bincode::deserialize_from(&mut reader)?;
+
+    if header.kind != T::KIND {
+        bail!("expected a {} bundle, found {}", T::KIND, header.kind);
+    }
+    if header.schema_version > SCHEMA_VERSION {
+        bail!(
+            "schema version {} is newer than the supported {SCHEMA_VERSION}, update xivhub",
+            header.schema_version
+        );
+    }
+    if sha256::digest(payload.as_slice()) != header.checksum {
+        bail!("checksum mismatch, the bundle is corrupted");
+    }
+
+    let value = if header.schema_version < SCHEMA_VERSION {
+        T::migrate(header.schema_version, &payload)?
+    } else {
+        bincode::deserialize(&payload)?
+    };
+
+    Ok((header, value))
+}
+
+pub fn write<T: Bundle>(
+    output: &Path,
+    value: &T,
+    game_version: &str,
+) -> color_eyre::Result<Header> {
+    let payload = bincode::serialize(value)?;
+    let header = Header {
+        kind: T::KIND.to_string(),
+        schema_version: SCHEMA_VERSION,
+        game_version: game_version.to_string(),
+        extracted_at: Utc::now(),
+        checksum: sha256::digest(payload.as_slice()),
+    };
+
+    let mut file = std::fs::File::create(output)?;
+    file.write_all(MAGIC)?;
+
+    let mut enc = zstd::stream::Encoder::new(file, 10)?;
+    bincode::serialize_into(&mut enc, &header)?;
+    bincode::serialize_into(&mut enc, &payload)?;
+    enc.finish()?;
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, write, Header, SCHEMA_VERSION};
+    use crate::entities::{ItemLocale, WorldBundle};
+
+    #[test]
+    fn versions() {
+        let dir = std::env::temp_dir();
+        let current = dir.join("xivhub_bundle_current.bin.zstd");
+        let legacy = dir.join("xivhub_bundle_legacy.bin.zstd");
+
+        let locales = vec![ItemLocale {
+            item_id: 5,
+            lang: "de".to_string(),
+            name: "Eisenerz".to_string(),
+            description: String::new(),
+            item_search_category_name: "Erze".to_string(),
+        }];
+
+        write(&current, &locales, "2023.10.03").unwrap();
+        let (header, read_back): (Header, Vec<ItemLocale>) = read(&current).unwrap();
+        assert_eq!(header.schema_version, SCHEMA_VERSION);
+        assert_eq!(header.game_version, "2023.10.03");
+        assert_eq!(read_back[0].name, "Eisenerz");
+
+        // a bundle of another kind is rejected.
+        assert!(read::<WorldBundle>(&current).is_err());
+
+        // headerless bundles are schema version 1.
+        let payload = bincode::serialize(&locales).unwrap();
+        std::fs::write(
+            &legacy,
+            zstd::stream::encode_all(payload.as_slice(), 3).unwrap(),
+        )
+        .unwrap();
+        let (header, read_back): (Header, Vec<ItemLocale>) = read(&legacy).unwrap();
+        assert_eq!(header.schema_version, 1);
+        assert_eq!(read_back[0].item_id, 5);
+
+        std::fs::remove_file(current).ok();
+        std::fs::remove_file(legacy).ok();
+    }
+}
@@ -0,0 +1,49 @@
+//! Opaque pagination cursors, the position of the last returned row encoded as hex json.
+
+use crate::error::AppError;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Write;
+
+#[must_use]
+pub fn encode<T: Serialize>(position: &T) -> String {
+    let json = serde_json::to_vec(position).expect("cursor serializes");
+    json.iter()
+        .fold(String::with_capacity(json.len() * 2), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+pub fn decode<T: DeserializeOwned>(cursor: &str) -> Result<T, AppError> {
+    let invalid = || AppError::BadRequest("invalid cursor".to_string());
+
+    let json = cursor
+        .as_bytes()
+        .chunks(2)
+        .map(|x| {
+            std::str::from_utf8(x)
+                .ok()
+                .filter(|x| x.len() == 2)
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+
+    serde_json::from_slice(&json).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn roundtrip() {
+        let cursor = encode(&(42, "Buyer Name".to_string()));
+        assert_eq!(
+            decode::<(i32, String)>(&cursor).unwrap(),
+            (42, "Buyer Name".to_string())
+        );
+        assert!(decode::<(i32, String)>("zz").is_err());
+        assert!(decode::<(i32, String)>("abc").is_err());
+    }
+}
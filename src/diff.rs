@@ -0,0 +1,200 @@
+//! Differences between two versions of the item data.
+
+use crate::entities::ItemInfo;
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Serialize, Default)]
+pub struct ItemDiff {
+    pub added: Vec<ItemInfo>,
+    pub removed: Vec<ItemInfo>,
+    /// Every changed item, renamed ones included.
+    pub changed: Vec<ItemChange>,
+    pub renamed: Vec<Rename>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemChange {
+    pub item_id: i32,
+    /// The new name.
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rename {
+    pub item_id: i32,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl ItemDiff {
+    /// Compares items by id, every list is sorted by id.
+    #[must_use]
+    pub fn new(old: &[ItemInfo], new: &[ItemInfo]) -> Self {
+        let mut old_by_id: HashMap<i32, &ItemInfo> = old.iter().map(|x| (x.item_id, x)).collect();
+        let mut diff = Self::default();
+
+        for item in new {
+            let Some(previous) = old_by_id.remove(&item.item_id) else {
+                diff.added.push(item.clone());
+                continue;
+            };
+
+            if previous.name != item.name {
+                diff.renamed.push(Rename {
+                    item_id: item.item_id,
+                    old_name: previous.name.clone(),
+                    new_name: item.name.clone(),
+                });
+            }
+
+            let fields = changed_fields(previous, item);
+            if fields.is_empty() {
+                diff.unchanged += 1;
+            } else {
+                diff.changed.push(ItemChange {
+                    item_id: item.item_id,
+                    name: item.name.clone(),
+                    fields,
+                });
+            }
+        }
+
+        diff.removed = old_by_id.into_values().cloned().collect();
+
+        diff.added.sort_by_key(|x| x.item_id);
+        diff.removed.sort_by_key(|x| x.item_id);
+        diff.changed.sort_by_key(|x| x.item_id);
+        diff.renamed.sort_by_key(|x| x.item_id);
+
+        diff
+    }
+}
+
+fn changed_fields(old: &ItemInfo, new: &ItemInfo) -> Vec<FieldChange> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(mut new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    old.into_iter()
+        .filter_map(|(field, old)| {
+            let new = new.remove(&field)?;
+            (old != new).then_some(FieldChange { field, old, new })
+        })
+        .collect()
+}
+
+impl fmt::Display for ItemDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} added, {} changed, {} renamed, {} removed, {} unchanged",
+            self.added.len(),
+            self.changed.len(),
+            self.renamed.len(),
+            self.removed.len(),
+            self.unchanged
+        )?;
+
+        for item in &self.added {
+            writeln!(f, "+ {} {}", item.item_id, item.name)?;
+        }
+        for item in &self.renamed {
+            writeln!(
+                f,
+                "> {} {} -> {}",
+                item.item_id, item.old_name, item.new_name
+            )?;
+        }
+        // renames are listed above.
+        for item in &self.changed {
+            let mut fields = item.fields.iter().filter(|x| x.field != "name").peekable();
+            if fields.peek().is_none() {
+                continue;
+            }
+
+            writeln!(f, "~ {} {}", item.item_id, item.name)?;
+            for change in fields {
+                writeln!(f, "    {}: {} -> {}", change.field, change.old, change.new)?;
+            }
+        }
+        for item in &self.removed {
+            writeln!(f, "- {} {}", item.item_id, item.name)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ItemDiff;
+    use crate::entities::ItemInfo;
+
+    fn item(item_id: i32, name: &str, stack_size: i32) -> ItemInfo {
+        ItemInfo {
+            item_id,
+            name: name.to_string(),
+            icon: String::new(),
+            icon_hd: String::new(),
+            description: String::new(),
+            item_kind_name: String::new(),
+            item_kind_id: 0,
+            item_search_category: 0,
+            item_search_category_iconhd: String::new(),
+            item_search_category_name: String::new(),
+            stack_size,
+            level_item: 1,
+            level_equip: 1,
+            materia_slot_count: 0,
+            rarity: 1,
+            can_be_hq: false,
+            price_mid: 0,
+            price_low: 0,
+            sold_by_vendor: false,
+        }
+    }
+
+    #[test]
+    fn diff_items() {
+        let old = [
+            item(1, "Iron Ore", 999),
+            item(2, "Copper Ore", 999),
+            item(3, "Gone", 1),
+        ];
+        let new = [
+            item(2, "Copper Ore", 9999),
+            item(1, "Iron Ore", 999),
+            item(4, "New", 1),
+        ];
+
+        let diff = ItemDiff::new(&old, &new);
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.added.iter().map(|x| x.item_id).collect::<Vec<_>>(),
+            [4]
+        );
+        assert_eq!(
+            diff.removed.iter().map(|x| x.item_id).collect::<Vec<_>>(),
+            [3]
+        );
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].item_id, 2);
+        assert_eq!(diff.changed[0].fields.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "stack_size");
+        assert!(diff.renamed.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Clone)]
@@ -10,6 +11,7 @@ pub struct Upload {
     pub world_id: i32,
     pub item_id: i32,
     pub upload_type: i32,
+    pub region: String,
     /// The item name.
     pub name: String,
     /// The item icon.
@@ -31,6 +33,16 @@ pub struct Listing {
     pub quantity: i32,
     pub retainer_city_id: i32,
     pub materia_count: i32,
+    pub region: String,
+    pub world_name: Option<String>,
+    /// The materia melded into the listed item.
+    pub materia: Json<Vec<ListingMateria>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ListingMateria {
+    pub slot_id: i32,
+    pub materia_id: i32,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -44,6 +56,8 @@ pub struct Purchase {
     pub purchase_time: DateTime<Utc>,
     pub quantity: i32,
     pub price_per_unit: i32,
+    pub region: String,
+    pub world_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -64,4 +78,59 @@ pub struct ItemInfo {
     pub materia_slot_count: i32,
     pub rarity: i32,
     pub can_be_hq: bool,
+    /// What a vendor sells the item for, only meaningful when `sold_by_vendor`.
+    pub price_mid: i32,
+    /// What a vendor pays for the item.
+    pub price_low: i32,
+    /// Whether a gil shop sells the item.
+    pub sold_by_vendor: bool,
+}
+
+/// The texts of an item in one client language, written by `bin/extract`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemLocale {
+    pub item_id: i32,
+    /// `en`, `ja`, `de` or `fr`.
+    pub lang: String,
+    pub name: String,
+    pub description: String,
+    pub item_search_category_name: String,
+}
+
+/// A recipe from the `Recipe` sheet, written by `bin/extract`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Recipe {
+    pub recipe_id: i32,
+    /// The crafted item.
+    pub item_id: i32,
+    pub result_quantity: i32,
+    pub craft_type: i32,
+    pub ingredients: Vec<RecipeIngredient>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecipeIngredient {
+    pub item_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DataCenter {
+    pub data_center_id: i32,
+    pub name: String,
+    pub region_id: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct World {
+    pub world_id: i32,
+    pub name: String,
+    pub data_center_id: i32,
+}
+
+/// The worlds bundle written by `bin/extract`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WorldBundle {
+    pub data_centers: Vec<DataCenter>,
+    pub worlds: Vec<World>,
 }
@@ -1,24 +1,198 @@
-use axum::response::IntoResponse;
+use axum::{response::IntoResponse, Json};
 use reqwest::StatusCode;
+use serde::Serialize;
+use std::{num::TryFromIntError, sync::Arc};
+use tracing::error;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
-#[derive(Debug)]
-pub struct AppError(pub color_eyre::eyre::Error);
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("not found")]
+    NotFound,
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("validation failed")]
+    Validation(#[from] ValidationErrors),
+    /// A json body that does not deserialize, reported like a failed validation.
+    #[error("validation failed")]
+    InvalidJson(FieldError),
+    #[error("db error: {0}")]
+    Database(Arc<sqlx::Error>),
+    #[error("timed out")]
+    Timeout,
+    #[error("something went wrong: {0}")]
+    Internal(color_eyre::eyre::Error),
+}
+
+impl AppError {
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Validation(_) | Self::InvalidJson(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Database(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The stable code sent in the `error` field of the response.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::BadRequest(_) => "bad_request",
+            Self::Validation(_) | Self::InvalidJson(_) => "validation_failed",
+            Self::Database(_) => "database_error",
+            Self::Timeout => "timeout",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
+        let status = self.status();
+        if status.is_server_error() {
+            error!("request failed: {}", self);
+        }
+
+        let fields = match &self {
+            Self::Validation(errors) => field_errors(errors),
+            Self::InvalidJson(error) => vec![error.clone()],
+            _ => Vec::new(),
+        };
+
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
+            status,
+            Json(ErrorResponse {
+                error: self.code(),
+                message: self.to_string(),
+                fields,
+            }),
         )
             .into_response()
     }
 }
 
-impl<E> From<E> for AppError
-where
-    E: Into<color_eyre::eyre::Error>,
-{
-    fn from(err: E) -> Self {
-        Self(err.into())
+impl From<Arc<sqlx::Error>> for AppError {
+    fn from(err: Arc<sqlx::Error>) -> Self {
+        match *err {
+            sqlx::Error::RowNotFound => Self::NotFound,
+            sqlx::Error::PoolTimedOut => Self::Timeout,
+            // query_canceled, raised when statement_timeout is hit.
+            sqlx::Error::Database(ref db) if db.code().as_deref() == Some("57014") => Self::Timeout,
+            _ => Self::Database(err),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        Arc::new(err).into()
+    }
+}
+
+impl From<color_eyre::eyre::Error> for AppError {
+    fn from(err: color_eyre::eyre::Error) -> Self {
+        Self::Internal(err)
+    }
+}
+
+impl From<TryFromIntError> for AppError {
+    fn from(err: TryFromIntError) -> Self {
+        Self::Internal(err.into())
+    }
+}
+
+/// The json body of every error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    /// Stable error code clients can match on, e.g. `not_found`.
+    pub error: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path to the field, e.g. `listings[3].price_per_unit`
+    pub field: String,
+    pub code: String,
+    pub message: Option<String>,
+}
+
+/// Flattens the nested validator errors into one entry per failed field, sorted by path.
+fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    fn walk(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+        for (name, kind) in errors.errors() {
+            let path = if prefix.is_empty() {
+                (*name).to_string()
+            } else {
+                format!("{prefix}.{name}")
+            };
+
+            match kind {
+                ValidationErrorsKind::Field(errors) => {
+                    out.extend(errors.iter().map(|e| FieldError {
+                        field: path.clone(),
+                        code: e.code.to_string(),
+                        message: e.message.as_ref().map(ToString::to_string),
+                    }));
+                }
+                ValidationErrorsKind::Struct(errors) => walk(&path, errors, out),
+                ValidationErrorsKind::List(list) => {
+                    for (i, errors) in list {
+                        walk(&format!("{path}[{i}]"), errors, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", errors, &mut out);
+    out.sort_by(|a, b| a.field.cmp(&b.field));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::field_errors;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Listing {
+        #[validate(range(min = 1))]
+        quantity: i32,
+    }
+
+    #[derive(Validate)]
+    struct Upload {
+        #[validate(range(min = 1))]
+        item_id: i32,
+        #[validate]
+        listings: Vec<Listing>,
+    }
+
+    #[test]
+    fn flattens_nested_errors() {
+        let upload = Upload {
+            item_id: 0,
+            listings: vec![Listing { quantity: 1 }, Listing { quantity: 0 }],
+        };
+
+        let fields: Vec<_> = field_errors(&upload.validate().unwrap_err())
+            .into_iter()
+            .map(|e| (e.field, e.code))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("item_id".to_string(), "range".to_string()),
+                ("listings[1].quantity".to_string(), "range".to_string()),
+            ]
+        );
     }
 }
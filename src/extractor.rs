@@ -0,0 +1,148 @@
+//! Extractors whose rejections are returned as [`AppError`] json bodies instead of axum's plain text.
+
+use crate::error::{AppError, FieldError};
+use axum::{
+    async_trait,
+    body::{Bytes, HttpBody},
+    extract::{FromRequest, FromRequestParts},
+    http::{header::CONTENT_TYPE, request::Parts, Request},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A json body, a body that does not deserialize is a `validation_failed` error with the path of the field.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .is_some_and(|x| x.starts_with("application/json"));
+        if !is_json {
+            return Err(AppError::BadRequest(
+                "expected `Content-Type: application/json`".to_string(),
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+
+        let de = &mut serde_json::Deserializer::from_slice(&bytes);
+        serde_path_to_error::deserialize(de).map(Self).map_err(|e| {
+            let code = if e.inner().is_data() {
+                "invalid"
+            } else {
+                "syntax"
+            };
+            AppError::InvalidJson(FieldError {
+                field: e.path().to_string(),
+                code: code.to_string(),
+                message: Some(e.inner().to_string()),
+            })
+        })
+    }
+}
+
+/// Query parameters, a query that does not deserialize is a `bad_request` error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for Query<T> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::from_request_parts(parts, state)
+            .await
+            .map(|x| Self(x.0))
+            .map_err(|e| AppError::BadRequest(e.body_text()))
+    }
+}
+
+/// Path parameters, e.g. a non numeric item id is a `bad_request` error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned + Send, S: Send + Sync> FromRequestParts<S> for Path<T> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Path::from_request_parts(parts, state)
+            .await
+            .map(|x| Self(x.0))
+            .map_err(|e| AppError::BadRequest(e.body_text()))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+    use crate::error::AppError;
+    use axum::{body::Body, extract::FromRequest, http::Request};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Listing {
+        #[allow(dead_code)]
+        price_per_unit: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Upload {
+        #[allow(dead_code)]
+        listings: Vec<Listing>,
+    }
+
+    async fn field(body: &'static str) -> Option<(String, String)> {
+        let req = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+
+        match Json::<Upload>::from_request(req, &()).await {
+            Err(AppError::InvalidJson(e)) => Some((e.field, e.code)),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn field_paths() {
+        assert_eq!(
+            field(r#"{"listings": [{"price_per_unit": 1}, {"price_per_unit": "10"}]}"#).await,
+            Some((
+                "listings[1].price_per_unit".to_string(),
+                "invalid".to_string()
+            ))
+        );
+        assert_eq!(
+            field(r#"{"listings": [{}]}"#).await,
+            Some(("listings[0]".to_string(), "invalid".to_string()))
+        );
+        assert_eq!(
+            field(r#"{"listings": "#).await.map(|x| x.1),
+            Some("syntax".to_string())
+        );
+    }
+}
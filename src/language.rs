@@ -0,0 +1,112 @@
+use crate::error::AppError;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::{header::ACCEPT_LANGUAGE, request::Parts},
+};
+use serde::{Deserialize, Serialize};
+
+/// The client languages item data is extracted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Ja,
+    De,
+    Fr,
+}
+
+impl Language {
+    pub const ALL: [Self; 4] = [Self::En, Self::Ja, Self::De, Self::Fr];
+
+    /// The value stored in the `lang` column of `item_info_locale`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+            Self::De => "de",
+            Self::Fr => "fr",
+        }
+    }
+
+    /// Parses a language tag, the subtag is ignored so `de-AT` is German.
+    #[must_use]
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next()?.trim();
+        Self::ALL
+            .into_iter()
+            .find(|x| x.as_str().eq_ignore_ascii_case(primary))
+    }
+
+    /// Picks the supported language with the highest weight in an `Accept-Language` header.
+    #[must_use]
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let Some(lang) = parts.next().and_then(Self::parse) else {
+                continue;
+            };
+            let weight = parts
+                .find_map(|x| x.trim().strip_prefix("q="))
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(1.0);
+
+            if weight > 0.0 && !matches!(best, Some((_, w)) if w >= weight) {
+                best = Some((lang, weight));
+            }
+        }
+
+        best.map(|(lang, _)| lang)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageQuery {
+    lang: Option<String>,
+}
+
+/// Takes the `lang` query parameter, then the `Accept-Language` header, then English.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Language {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<LanguageQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+        if let Some(lang) = query.lang {
+            return Self::parse(&lang)
+                .ok_or_else(|| AppError::BadRequest(format!("unknown language {lang}")));
+        }
+
+        Ok(parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|x| x.to_str().ok())
+            .and_then(Self::from_accept_language)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Language;
+
+    #[test]
+    fn accept_language() {
+        assert_eq!(
+            Language::from_accept_language("fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5"),
+            Some(Language::Fr)
+        );
+        assert_eq!(
+            Language::from_accept_language("es, ja;q=0.2, de-DE;q=0.7"),
+            Some(Language::De)
+        );
+        assert_eq!(Language::from_accept_language("es, it;q=0.5"), None);
+    }
+}
@@ -7,24 +7,35 @@
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::missing_panics_doc)]
 
+use language::Language;
 use moka::future::Cache;
 use routes::{
     item::{ListingsResponse, PurchasesResponse},
     stats::Stats,
 };
 pub use sqlx::PgPool;
+use world::Scope;
 
+pub mod archive;
+pub mod bundle;
+pub mod cursor;
+pub mod diff;
 pub mod entities;
 pub mod error;
+pub mod extractor;
+pub mod language;
+pub mod rollup;
 pub mod routes;
 pub mod util;
+pub mod world;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub pool: PgPool,
     // Since stats is just 1 object, we make a simple cache.
     pub stats_cache: Cache<(), Stats>,
-    pub item_listings_cache: Cache<i32, ListingsResponse>,
+    // Keyed by item, scope and language, updates invalidate every entry of the item.
+    pub item_listings_cache: Cache<(i32, Scope, Language), ListingsResponse>,
     // For now, only page 0 is cached. The only page used by the current frontend.
-    pub item_purchase_cache: Cache<i32, PurchasesResponse>,
+    pub item_purchase_cache: Cache<(i32, Language), PurchasesResponse>,
 }
@@ -10,26 +10,35 @@
 #![allow(clippy::missing_panics_doc)]
 
 use axum::{
+    http::Request,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
 use axum_prometheus::PrometheusMetricLayer;
 use moka::future::Cache;
 use reqwest::Method;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::{postgres::PgPoolOptions, Executor, PgPool};
 use std::{net::SocketAddr, time::Duration};
 use tokio_cron_scheduler::{Job, JobScheduler};
 use tower_http::{
     cors::{Any, CorsLayer},
-    timeout::TimeoutLayer,
     trace::TraceLayer,
 };
 use tracing::error;
 use xivhub_market::{
+    error::AppError,
+    rollup,
     routes::{self},
     AppState,
 };
 
+/// Requests still running after this get a `timeout` error.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Shorter than `REQUEST_TIMEOUT` so a slow query is cancelled instead of left running.
+const STATEMENT_TIMEOUT: &str = "4s";
+
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
     // Better error reporting whe compiling if we are not inside a macro.
@@ -44,13 +53,12 @@ async fn run() -> color_eyre::Result<()> {
 
     let (prometheus_layer, metrics_handle) = PrometheusMetricLayer::pair();
 
-    let pool = PgPoolOptions::new()
-        .max_connections(
-            std::env::var("DATABASE_MAX_CONNECTIONS")
-                .map(|x| x.parse().expect("valid number"))
-                .unwrap_or(30),
-        )
-        .connect(&std::env::var("DATABASE_URL")?)
+    let database_url = std::env::var("DATABASE_URL")?;
+    let pool = connect(&database_url).await?;
+    // the jobs may run for longer than a request, they get their own connections without the timeout.
+    let jobs_pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
         .await?;
 
     let items_cache_capacity = std::env::var("XIVHUB_ITEMS_CACHE_SIZE")
@@ -63,11 +71,13 @@ async fn run() -> color_eyre::Result<()> {
             .name("item_listings_cache")
             .time_to_idle(Duration::from_secs(60 * 60 * 6)) // 6 hours, updates invalidate the entry
             .max_capacity(items_cache_capacity)
+            .support_invalidation_closures()
             .build(),
         item_purchase_cache: Cache::builder()
             .name("item_purchase_cache")
             .time_to_idle(Duration::from_secs(60 * 60 * 6)) // 6 hours, updates invalidate the entry
             .max_capacity(items_cache_capacity)
+            .support_invalidation_closures()
             .build(),
         stats_cache: Cache::builder()
             .name("stats_cache")
@@ -76,28 +86,11 @@ async fn run() -> color_eyre::Result<()> {
             .build(),
     };
 
-    let sched = JobScheduler::new().await?;
-
-    let sched_pool = pool.clone();
-    sched
-        .add(Job::new_repeated_async(
-            Duration::from_secs(60 * 30),
-            move |_, _sched| {
-                let sched_pool = sched_pool.clone();
-                Box::pin(async move {
-                    let result = sqlx::query!(
-                        "delete from purchase where purchase_time < NOW() - INTERVAL '1 months'"
-                    )
-                    .execute(&sched_pool)
-                    .await;
-
-                    if let Err(e) = result {
-                        error!("task (sched) error: {}", e);
-                    }
-                })
-            },
-        )?)
-        .await?;
+    // the scheduler was never started before, so the first purge deletes every purchase older than the retention.
+    // it goes one day per transaction, set XIVHUB_ARCHIVE_DIR beforehand to keep the deleted rows.
+    let sched = schedule_jobs(&jobs_pool).await?;
+    sched.start().await?;
+    tracing::info!("started the maintenance jobs");
 
     // build our application with a route
     let app = Router::new()
@@ -107,9 +100,25 @@ async fn run() -> color_eyre::Result<()> {
         .route("/upload", post(routes::upload::listings))
         .route("/stats", get(routes::stats::stats))
         .route("/cache_stats", get(routes::stats::cache_stats))
+        .route("/worlds", get(routes::world::worlds))
+        .route("/datacenters", get(routes::world::data_centers))
+        .route("/regions", get(routes::world::regions))
+        .route("/categories", get(routes::item::categories))
         .route("/item", get(routes::item::list))
+        .route("/item/autocomplete", get(routes::item::autocomplete))
         .route("/item/:id", get(routes::item::listings))
+        .route(
+            "/item/:id/listings/history",
+            get(routes::item::listings_history),
+        )
+        .route("/item/:id/craft_cost", get(routes::craft::craft_cost))
+        .route("/item/:id/price_stats", get(routes::item::price_stats))
+        .route("/item/:id/trends", get(routes::item::trends))
         .route("/item/:id/purchases", get(routes::item::purchases))
+        .route(
+            "/item/:id/purchases/ohlc",
+            get(routes::item::purchases_ohlc),
+        )
         .route(
             "/item/:id/purchases_by_day",
             get(routes::item::purchases_by_day),
@@ -118,9 +127,10 @@ async fn run() -> color_eyre::Result<()> {
             "/item/:id/uploads",
             get(routes::item::get_item_upload_dates),
         )
+        .route("/vendor_flips", get(routes::vendor::vendor_flips))
         .route("/metrics", get(|| async move { metrics_handle.render() }))
         .layer(TraceLayer::new_for_http())
-        .layer(TimeoutLayer::new(Duration::from_secs(5)))
+        .layer(middleware::from_fn(timeout))
         .layer(
             CorsLayer::new()
                 .allow_methods([Method::GET, Method::POST])
@@ -142,3 +152,116 @@ async fn run() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+/// The pool of the api, its queries are cancelled after `STATEMENT_TIMEOUT`.
+async fn connect(database_url: &str) -> Result<PgPool, sqlx::Error> {
+    PgPoolOptions::new()
+        .max_connections(
+            std::env::var("DATABASE_MAX_CONNECTIONS")
+                .map(|x| x.parse().expect("valid number"))
+                .unwrap_or(30),
+        )
+        .after_connect(|conn, _| {
+            Box::pin(async move {
+                conn.execute(format!("SET statement_timeout = '{STATEMENT_TIMEOUT}'").as_str())
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(database_url)
+        .await
+}
+
+/// Like tower-http's `TimeoutLayer`, with the json `timeout` error instead of an empty 408.
+async fn timeout<B>(req: Request<B>, next: Next<B>) -> Response {
+    tokio::time::timeout(REQUEST_TIMEOUT, next.run(req))
+        .await
+        .unwrap_or_else(|_| AppError::Timeout.into_response())
+}
+
+/// Registers the periodic maintenance jobs, the returned scheduler still needs to be started.
+async fn schedule_jobs(pool: &PgPool) -> color_eyre::Result<JobScheduler> {
+    let listing_history_days: i32 = std::env::var("XIVHUB_LISTING_HISTORY_DAYS")
+        .map(|x| x.parse().expect("valid number"))
+        .unwrap_or(14);
+    let purchase_retention_days: i32 = std::env::var("XIVHUB_PURCHASE_RETENTION_DAYS")
+        .map(|x| x.parse().expect("valid number"))
+        .unwrap_or(30);
+    let archive_dir = std::env::var("XIVHUB_ARCHIVE_DIR")
+        .ok()
+        .map(std::path::PathBuf::from);
+
+    let sched = JobScheduler::new().await?;
+
+    let sched_pool = pool.clone();
+    sched
+        .add(Job::new_repeated_async(
+            Duration::from_secs(60 * 30),
+            move |_, _sched| {
+                let sched_pool = sched_pool.clone();
+                let archive_dir = archive_dir.clone();
+                Box::pin(async move {
+                    // the daily rollups keep the purged days.
+                    if let Err(e) =
+                        rollup::purge(&sched_pool, purchase_retention_days, archive_dir).await
+                    {
+                        error!("task (sched) error: {}", e);
+                    }
+                })
+            },
+        )?)
+        .await?;
+
+    let sched_pool = pool.clone();
+    sched
+        .add(Job::new_repeated_async(
+            Duration::from_secs(60 * 30),
+            move |_, _sched| {
+                let sched_pool = sched_pool.clone();
+                Box::pin(async move {
+                    let result = sqlx::query!(
+                        "delete from listing_history where upload_time < NOW() - make_interval(days => $1)",
+                        listing_history_days
+                    )
+                    .execute(&sched_pool)
+                    .await;
+
+                    if let Err(e) = result {
+                        error!("task (sched) error: {}", e);
+                        return;
+                    }
+
+                    // materia of listings that are neither current nor in the history anymore.
+                    let result = sqlx::query!(
+                        "delete from listing_materia m
+                        where not exists (select 1 from listing l where l.upload_id = m.upload_id)
+                        and not exists (select 1 from listing_history h where h.upload_id = m.upload_id)"
+                    )
+                    .execute(&sched_pool)
+                    .await;
+
+                    if let Err(e) = result {
+                        error!("task (sched) error: {}", e);
+                    }
+                })
+            },
+        )?)
+        .await?;
+
+    let sched_pool = pool.clone();
+    sched
+        .add(Job::new_repeated_async(
+            Duration::from_secs(60 * 5),
+            move |_, _sched| {
+                let sched_pool = sched_pool.clone();
+                Box::pin(async move {
+                    if let Err(e) = rollup::refresh(&sched_pool).await {
+                        error!("task (sched) error: {}", e);
+                    }
+                })
+            },
+        )?)
+        .await?;
+
+    Ok(sched)
+}
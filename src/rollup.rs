@@ -0,0 +1,244 @@
+//! Daily rollups of the `purchase` and `upload` tables, read by the per-day endpoints.
+//!
+//! A refresh only recomputes what the uploads since the previous refresh touched:
+//! the purchases a history upload added carry its id,
+//! so the days from the oldest of them are rebuilt for its item and world.
+//!
+//! `purchase_daily` is never purged, it keeps the history once the raw purchases are deleted by `purge`.
+
+use crate::archive::{self, ArchivedPurchase};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use tracing::info;
+
+/// Uploads newer than this may still be in an open transaction, they are left for the next refresh.
+const REFRESH_LAG_SECONDS: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Refresh {
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Item and world pairs whose purchases were rebuilt.
+    pub item_worlds: usize,
+}
+
+/// Brings `purchase_daily` and `upload_daily` up to date.
+pub async fn refresh(db: &PgPool) -> Result<Refresh, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let refresh = refresh_in(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(refresh)
+}
+
+/// Refreshes the rollups, then deletes the purchases of the days older than `retention_days`.
+/// With an `archive_dir` they are written to the archive first, nothing is deleted if that fails.
+///
+/// Whole UTC days are deleted so the days left in `purchase` can still be rebuilt,
+/// one transaction per day keeps a large backlog from being held in memory at once.
+pub async fn purge(
+    db: &PgPool,
+    retention_days: i32,
+    archive_dir: Option<PathBuf>,
+) -> color_eyre::Result<u64> {
+    let mut tx = db.begin().await?;
+    refresh_in(&mut tx).await?;
+
+    let cutoff = sqlx::query_scalar!(
+        r#"UPDATE rollup_state SET purged_before = GREATEST(purged_before, date(timezone('UTC', NOW())) - $1::INT)
+        WHERE name = 'daily'
+        RETURNING purged_before"#,
+        retention_days
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    let mut deleted = 0;
+    let mut after = None;
+    while let Some(day) = next_purchase_day(db, after, cutoff).await? {
+        deleted += purge_day(db, day, archive_dir.as_deref()).await?;
+        after = Some(day);
+    }
+
+    info!("Purged {deleted} purchases before {cutoff}");
+
+    Ok(deleted)
+}
+
+/// The first day after `after` and before `until` with purchases.
+async fn next_purchase_day(
+    db: &PgPool,
+    after: Option<NaiveDate>,
+    until: NaiveDate,
+) -> Result<Option<NaiveDate>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT date(timezone('UTC', MIN(purchase_time))) FROM purchase
+        WHERE ($1::DATE IS NULL OR purchase_time >= ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC')
+        AND purchase_time < $2::DATE::TIMESTAMP AT TIME ZONE 'UTC'",
+        after,
+        until
+    )
+    .fetch_one(db)
+    .await
+}
+
+async fn purge_day(
+    db: &PgPool,
+    day: NaiveDate,
+    archive_dir: Option<&Path>,
+) -> color_eyre::Result<u64> {
+    let mut tx = db.begin().await?;
+
+    let Some(dir) = archive_dir else {
+        let deleted = sqlx::query!(
+            "DELETE FROM purchase
+            WHERE purchase_time >= $1::DATE::TIMESTAMP AT TIME ZONE 'UTC'
+            AND purchase_time < ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC'",
+            day
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+
+        return Ok(deleted);
+    };
+
+    // the deleted rows are archived, not a select before the delete that could miss a concurrent upload.
+    let mut purchases = sqlx::query_as!(
+        ArchivedPurchase,
+        "DELETE FROM purchase
+        WHERE purchase_time >= $1::DATE::TIMESTAMP AT TIME ZONE 'UTC'
+        AND purchase_time < ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC'
+        RETURNING upload_id, item_id, world_id, buyer_name, hq, on_mannequin, purchase_time,
+        quantity, price_per_unit, region",
+        day
+    )
+    .fetch_all(&mut tx)
+    .await?;
+    if purchases.is_empty() {
+        return Ok(0);
+    }
+    purchases.sort_by_key(|x| x.purchase_time);
+
+    let deleted = purchases.len() as u64;
+    let dir = dir.to_path_buf();
+    let appended =
+        tokio::task::spawn_blocking(move || archive::append(&dir, day, &purchases)).await??;
+
+    if let Err(err) = tx.commit().await {
+        tokio::task::spawn_blocking(move || appended.rollback()).await??;
+        return Err(err.into());
+    }
+    info!(
+        "Archived {deleted} purchases to {}",
+        appended.path.display()
+    );
+
+    Ok(deleted)
+}
+
+async fn refresh_in(tx: &mut Transaction<'_, Postgres>) -> Result<Refresh, sqlx::Error> {
+    let start = Instant::now();
+
+    // the lock keeps concurrent refreshes from rebuilding the same days.
+    let state = sqlx::query!(
+        r#"SELECT refreshed_until, purged_before, NOW() - make_interval(secs => $1) as "until!"
+        FROM rollup_state WHERE name = 'daily' FOR UPDATE"#,
+        REFRESH_LAG_SECONDS
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let (from, until) = (state.refreshed_until, state.until);
+
+    // the purged days are only in the rollup, they must not be rebuilt.
+    let affected = sqlx::query!(
+        r#"SELECT p.item_id, p.world_id, GREATEST(MIN(date(timezone('UTC', p.purchase_time))), $3) as "from_day!"
+        FROM upload u
+        JOIN purchase p ON p.upload_id = u.id
+        WHERE u.upload_type = 1 AND u.upload_time > $1 AND u.upload_time <= $2
+        GROUP BY p.item_id, p.world_id"#,
+        from,
+        until,
+        state.purged_before
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut item_ids = Vec::with_capacity(affected.len());
+    let mut world_ids = Vec::with_capacity(affected.len());
+    let mut from_days: Vec<NaiveDate> = Vec::with_capacity(affected.len());
+    for row in &affected {
+        item_ids.push(row.item_id);
+        world_ids.push(row.world_id);
+        from_days.push(row.from_day);
+    }
+
+    sqlx::query!(
+        "DELETE FROM purchase_daily d
+        USING UNNEST($1::INT[], $2::INT[], $3::DATE[]) AS a(item_id, world_id, from_day)
+        WHERE d.item_id = a.item_id AND d.world_id = a.world_id AND d.day >= a.from_day",
+        &item_ids,
+        &world_ids,
+        &from_days
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO purchase_daily
+        (item_id, world_id, region, day, hq, high, low, price_sum, turnover, quantity, trades)
+        SELECT p.item_id, p.world_id, MIN(p.region), date(timezone('UTC', p.purchase_time)), p.hq,
+        MAX(p.price_per_unit), MIN(p.price_per_unit), SUM(p.price_per_unit::BIGINT),
+        SUM(p.price_per_unit::BIGINT * p.quantity), SUM(p.quantity::BIGINT), COUNT(*)
+        FROM purchase p
+        JOIN UNNEST($1::INT[], $2::INT[], $3::DATE[]) AS a(item_id, world_id, from_day)
+            ON p.item_id = a.item_id AND p.world_id = a.world_id
+        WHERE date(timezone('UTC', p.purchase_time)) >= a.from_day
+        GROUP BY p.item_id, p.world_id, date(timezone('UTC', p.purchase_time)), p.hq",
+        &item_ids,
+        &world_ids,
+        &from_days
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // the day of the previous refresh was only counted up to it.
+    let from_day = from.date_naive();
+    sqlx::query!(
+        "INSERT INTO upload_daily (day, uploads)
+        SELECT date(timezone('UTC', upload_time)), COUNT(*) FROM upload
+        WHERE upload_time >= $1::DATE::TIMESTAMP AT TIME ZONE 'UTC' AND upload_time <= $2
+        GROUP BY date(timezone('UTC', upload_time))
+        ON CONFLICT (day) DO UPDATE SET uploads = EXCLUDED.uploads",
+        from_day,
+        until
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE rollup_state SET refreshed_until = $1 WHERE name = 'daily'",
+        until
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let refresh = Refresh {
+        from,
+        until,
+        item_worlds: affected.len(),
+    };
+    info!(
+        "Refreshed the daily rollups for {} item worlds in {:?}",
+        refresh.item_worlds,
+        start.elapsed()
+    );
+
+    Ok(refresh)
+}
@@ -0,0 +1,159 @@
+use crate::{
+    entities::ItemInfo,
+    error::AppError,
+    extractor::{Path, Query},
+    language::Language,
+    util::fetch_item_info,
+    world::Region,
+    AppState,
+};
+use axum::{extract::State, Json};
+use axum_prometheus::metrics::histogram;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Purchases older than this are not used to price the crafted item.
+const SALE_DAYS: i32 = 7;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct CraftCostQuery {
+    /// Only use listings and purchases of this world.
+    pub world: Option<i32>,
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CraftCostResponse {
+    pub item: ItemInfo,
+    pub sales: SaleStats,
+    /// An item can have a recipe for multiple crafting classes.
+    pub recipes: Vec<RecipeCost>,
+}
+
+/// Purchases of the crafted item in the last `SALE_DAYS` days.
+#[derive(Debug, Serialize)]
+pub struct SaleStats {
+    pub count: i64,
+    pub average: Option<f64>,
+    pub median: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecipeCost {
+    pub recipe_id: i32,
+    pub craft_type: i32,
+    pub result_quantity: i32,
+    pub ingredients: Vec<IngredientCost>,
+    /// None when an ingredient has no listing.
+    pub total_cost: Option<i64>,
+    pub cost_per_unit: Option<f64>,
+    /// The median sale price minus `cost_per_unit`.
+    pub profit_per_unit: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngredientCost {
+    pub item_id: i32,
+    pub name: Option<String>,
+    pub quantity: i32,
+    /// The cheapest listing, None when the ingredient is not listed.
+    pub unit_price: Option<i32>,
+    pub world_id: Option<i32>,
+    pub total: Option<i64>,
+}
+
+/// compares the cost of crafting an item from listed ingredients with its recent sale prices
+pub async fn craft_cost(
+    State(state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Query(query): Query<CraftCostQuery>,
+    lang: Language,
+) -> Result<Json<CraftCostResponse>, AppError> {
+    let region = Region::resolve(query.region, query.world)?;
+
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+    let start = Instant::now();
+    let rows = sqlx::query!(
+        r#"SELECT r.recipe_id, r.craft_type, r.result_quantity, ri.item_id, ri.quantity,
+        COALESCE(t.name, i.name) as name, c.price_per_unit as "price_per_unit?", c.world_id as "world_id?"
+        FROM recipe r
+        JOIN recipe_ingredient ri ON ri.recipe_id = r.recipe_id
+        LEFT JOIN item_info i ON i.item_id = ri.item_id
+        LEFT JOIN item_info_locale t ON t.item_id = ri.item_id AND t.lang = $4
+        LEFT JOIN LATERAL (
+            SELECT l.price_per_unit, l.world_id FROM listing l
+            WHERE l.item_id = ri.item_id AND l.region = $2 AND ($3::INT IS NULL OR l.world_id = $3)
+            ORDER BY l.price_per_unit ASC LIMIT 1
+        ) c ON TRUE
+        WHERE r.item_id = $1
+        ORDER BY r.recipe_id, ri.item_id"#,
+        item_id,
+        region.as_str(),
+        query.world,
+        lang.as_str()
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let sales = sqlx::query_as!(
+        SaleStats,
+        r#"SELECT COUNT(*) as "count!", AVG(price_per_unit)::FLOAT8 as average,
+        percentile_cont(0.5) WITHIN GROUP (ORDER BY price_per_unit) as median
+        FROM purchase
+        WHERE item_id = $1 AND region = $2 AND ($3::INT IS NULL OR world_id = $3)
+        AND purchase_time > NOW() - make_interval(days => $4)"#,
+        item_id,
+        region.as_str(),
+        query.world,
+        SALE_DAYS
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_craft_cost");
+
+    let mut recipes: Vec<RecipeCost> = Vec::new();
+    for row in rows {
+        if recipes.last().map(|x| x.recipe_id) != Some(row.recipe_id) {
+            recipes.push(RecipeCost {
+                recipe_id: row.recipe_id,
+                craft_type: row.craft_type,
+                result_quantity: row.result_quantity,
+                ingredients: Vec::new(),
+                total_cost: None,
+                cost_per_unit: None,
+                profit_per_unit: None,
+            });
+        }
+
+        if let Some(recipe) = recipes.last_mut() {
+            recipe.ingredients.push(IngredientCost {
+                item_id: row.item_id,
+                name: row.name,
+                quantity: row.quantity,
+                unit_price: row.price_per_unit,
+                world_id: row.world_id,
+                total: row
+                    .price_per_unit
+                    .map(|x| i64::from(x) * i64::from(row.quantity)),
+            });
+        }
+    }
+
+    for recipe in &mut recipes {
+        recipe.total_cost = recipe.ingredients.iter().map(|x| x.total).sum();
+        #[allow(clippy::cast_precision_loss)]
+        let cost_per_unit = recipe
+            .total_cost
+            .map(|x| x as f64 / f64::from(recipe.result_quantity.max(1)));
+        recipe.cost_per_unit = cost_per_unit;
+        recipe.profit_per_unit = sales.median.zip(cost_per_unit).map(|(a, b)| a - b);
+    }
+
+    Ok(Json(CraftCostResponse {
+        item,
+        sales,
+        recipes,
+    }))
+}
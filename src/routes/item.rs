@@ -1,52 +1,253 @@
 use crate::{
-    entities::{ItemInfo, Listing, Purchase},
+    cursor,
+    entities::{ItemInfo, Listing, ListingMateria, Purchase},
     error::AppError,
-    util::fetch_item_info,
+    extractor::{Path, Query},
+    language::Language,
+    util::{escape_like, fetch_item_info, parse_duration},
+    world::{Region, Scope},
     AppState,
 };
-use axum::{
-    extract::{Path, Query, State},
-    Json,
-};
+use axum::{extract::State, Json};
 use axum_prometheus::metrics::{histogram, increment_counter};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use sqlx::PgPool;
+use std::{collections::HashMap, time::Instant};
+use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Serialize, Clone)]
 pub struct ListingsResponse {
     pub item: ItemInfo,
     pub listings: Vec<Listing>,
+    /// The cheapest listing of each world in scope, sorted by price.
+    pub cheapest_by_world: Vec<Listing>,
+}
+
+impl ListingsResponse {
+    fn new(item: ItemInfo, listings: Vec<Listing>) -> Self {
+        let mut cheapest: HashMap<i32, &Listing> = HashMap::new();
+        for listing in &listings {
+            let entry = cheapest.entry(listing.world_id).or_insert(listing);
+            if listing.price_per_unit < entry.price_per_unit {
+                *entry = listing;
+            }
+        }
+        let mut cheapest_by_world: Vec<Listing> = cheapest.into_values().cloned().collect();
+        cheapest_by_world.sort_by_key(|x| (x.price_per_unit, x.world_id));
+
+        Self {
+            item,
+            listings,
+            cheapest_by_world,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingSort {
+    /// Cheapest unit price first.
+    Price,
+    /// Cheapest stack (price * quantity) first.
+    Total,
+    /// Most recently reviewed first.
+    LastReviewTime,
+}
+
+/// Filters applied in the listings query, when all are empty the result is cached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ListingFilters {
+    materia: Option<i32>,
+    hq: Option<bool>,
+    min_quantity: Option<i32>,
+    max_price: Option<i32>,
+    retainer_city_id: Option<i32>,
+    sort: Option<ListingSort>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ListingsQuery {}
+pub struct ListingsQuery {
+    /// Only return listings with this materia melded.
+    pub materia: Option<i32>,
+    pub hq: Option<bool>,
+    pub min_quantity: Option<i32>,
+    pub max_price: Option<i32>,
+    pub retainer_city_id: Option<i32>,
+    /// Defaults to world then price.
+    pub sort: Option<ListingSort>,
+    pub world: Option<i32>,
+    /// Data center id or name.
+    pub dc: Option<String>,
+    /// A market (`global`, `cn`, `kr`) or a game region (`europe`, `north-america`...).
+    pub region: Option<String>,
+}
+
+impl ListingsQuery {
+    const fn filters(&self) -> ListingFilters {
+        ListingFilters {
+            materia: self.materia,
+            hq: self.hq,
+            min_quantity: self.min_quantity,
+            max_price: self.max_price,
+            retainer_city_id: self.retainer_city_id,
+            sort: self.sort,
+        }
+    }
+
+    /// The narrowest scope given wins: world, then data center, then region.
+    /// A world is checked against the region, it cannot be combined with a data center.
+    async fn scope(&self, db: &PgPool) -> Result<Scope, AppError> {
+        let region = match &self.region {
+            Some(region) => Some(
+                Scope::parse_region(region)
+                    .ok_or_else(|| AppError::BadRequest(format!("unknown region: {region}")))?,
+            ),
+            None => None,
+        };
+
+        if let Some(world_id) = self.world {
+            if self.dc.is_some() {
+                return Err(AppError::BadRequest(
+                    "world and dc cannot be combined".to_string(),
+                ));
+            }
+
+            Region::resolve(region.and_then(Scope::market), Some(world_id))?;
+
+            let known = sqlx::query!(
+                r#"SELECT EXISTS(SELECT 1 FROM world) as "worlds_imported!",
+                (SELECT d.region_id FROM world w JOIN data_center d ON d.data_center_id = w.data_center_id
+                    WHERE w.world_id = $1) as region_id"#,
+                world_id
+            )
+            .fetch_one(db)
+            .await?;
+            if known.worlds_imported && known.region_id.is_none() {
+                return Err(AppError::BadRequest(format!("unknown world {world_id}")));
+            }
+            if let Some(game_region) = region.and_then(Scope::game_region) {
+                if known.region_id != Some(game_region) {
+                    return Err(AppError::BadRequest(format!(
+                        "world {world_id} is not in region {}",
+                        self.region.as_deref().unwrap_or_default()
+                    )));
+                }
+            }
+
+            return Ok(Scope::World(world_id));
+        }
+
+        if let Some(dc) = &self.dc {
+            let id = match dc.parse() {
+                Ok(id) => Some(id),
+                Err(_) => sqlx::query!(
+                    "SELECT data_center_id FROM data_center WHERE LOWER(name) = LOWER($1)",
+                    dc
+                )
+                .fetch_optional(db)
+                .await?
+                .map(|x| x.data_center_id),
+            };
+            return id
+                .map(Scope::DataCenter)
+                .ok_or_else(|| AppError::BadRequest(format!("unknown data center: {dc}")));
+        }
+
+        Ok(region.unwrap_or_default())
+    }
+}
+
+async fn fetch_listings(
+    item_id: i32,
+    scope: Scope,
+    filters: ListingFilters,
+    db: &PgPool,
+) -> Result<Vec<Listing>, sqlx::Error> {
+    let sort = filters.sort.map(|sort| match sort {
+        ListingSort::Price => "price",
+        ListingSort::Total => "total",
+        ListingSort::LastReviewTime => "last_review_time",
+    });
+
+    sqlx::query_as!(
+        Listing,
+        r#"SELECT l.upload_id, l.world_id, l.item_id, l.hq, l.seller_id, l.retainer_id, l.retainer_name,
+        l.creator_id, l.creator_name, l.last_review_time, l.price_per_unit, l.quantity,
+        l.retainer_city_id, l.materia_count, l.region, w.name as "world_name?",
+        COALESCE((
+            SELECT json_agg(json_build_object('slot_id', m.slot_id, 'materia_id', m.materia_id) ORDER BY m.slot_id)
+            FROM listing_materia m WHERE m.upload_id = l.upload_id AND m.listing_index = l.listing_index
+        ), '[]') as "materia!: sqlx::types::Json<Vec<ListingMateria>>"
+        FROM listing l
+        LEFT JOIN world w ON w.world_id = l.world_id
+        WHERE l.item_id = $1
+        AND ($2::TEXT IS NULL OR l.region = $2)
+        AND ($3::INT IS NULL OR l.world_id = $3)
+        AND ($4::INT IS NULL OR w.data_center_id = $4)
+        AND ($5::INT IS NULL OR w.data_center_id IN (SELECT data_center_id FROM data_center WHERE region_id = $5))
+        AND ($6::INT IS NULL OR EXISTS (
+            SELECT 1 FROM listing_materia m
+            WHERE m.upload_id = l.upload_id AND m.listing_index = l.listing_index AND m.materia_id = $6
+        ))
+        AND ($7::BOOL IS NULL OR l.hq = $7)
+        AND ($8::INT IS NULL OR l.quantity >= $8)
+        AND ($9::INT IS NULL OR l.price_per_unit <= $9)
+        AND ($10::INT IS NULL OR l.retainer_city_id = $10)
+        ORDER BY
+            CASE WHEN $11 = 'price' THEN l.price_per_unit END ASC,
+            CASE WHEN $11 = 'total' THEN l.price_per_unit::BIGINT * l.quantity END ASC,
+            CASE WHEN $11 = 'last_review_time' THEN l.last_review_time END DESC,
+            l.world_id ASC, l.price_per_unit ASC"#,
+        item_id,
+        scope.market().map(Region::as_str),
+        scope.world(),
+        scope.data_center(),
+        scope.game_region(),
+        filters.materia,
+        filters.hq,
+        filters.min_quantity,
+        filters.max_price,
+        filters.retainer_city_id,
+        sort as Option<&str>,
+    )
+    .fetch_all(db)
+    .await
+}
 
 pub async fn listings(
     State(state): State<AppState>,
     Path(item_id): Path<i32>,
+    Query(query): Query<ListingsQuery>,
+    lang: Language,
 ) -> Result<Json<ListingsResponse>, AppError> {
     increment_counter!("xivhub_listings_request");
 
     let listings_time = Instant::now();
 
-    let listings = state
-        .item_listings_cache
-        .try_get_with(item_id, async {
-            increment_counter!("xivhub_listings_request_cache_miss");
-            let listings = sqlx::query_as!(
-            Listing,
-            "SELECT * FROM listing WHERE item_id = $1 ORDER BY world_id ASC, price_per_unit ASC",
-            item_id
-        )
-            .fetch_all(&state.pool)
-            .await?;
+    let scope = query.scope(&state.pool).await?;
+    let filters = query.filters();
 
-            let item = fetch_item_info(item_id, &state.pool).await?;
+    // only the unfiltered listings are cached.
+    let listings = if filters == ListingFilters::default() {
+        state
+            .item_listings_cache
+            .try_get_with((item_id, scope, lang), async {
+                increment_counter!("xivhub_listings_request_cache_miss");
+                let listings = fetch_listings(item_id, scope, filters, &state.pool).await?;
+                let item = fetch_item_info(item_id, lang, &state.pool).await?;
 
-            Ok::<_, sqlx::Error>(ListingsResponse { item, listings })
-        })
-        .await?;
+                Ok::<_, sqlx::Error>(ListingsResponse::new(item, listings))
+            })
+            .await?
+    } else {
+        increment_counter!("xivhub_listings_request_cache_miss");
+        let listings = fetch_listings(item_id, scope, filters, &state.pool).await?;
+        let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+        ListingsResponse::new(item, listings)
+    };
 
     let listings_time = listings_time.elapsed();
     histogram!("xivhub_get_item_listings_time", listings_time);
@@ -54,71 +255,312 @@ pub async fn listings(
     Ok(Json(listings))
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct ListingSnapshot {
+    pub upload_id: Uuid,
+    pub upload_time: DateTime<Utc>,
+    pub world_id: i32,
+    pub listings: Vec<Listing>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ListingHistoryResponse {
+    pub item: ItemInfo,
+    pub snapshots: Vec<ListingSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingHistoryQuery {
+    pub world_id: Option<i32>,
+    pub region: Option<Region>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// returns the past listing snapshots of an item, newest first
+pub async fn listings_history(
+    State(state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Query(query): Query<ListingHistoryQuery>,
+    lang: Language,
+) -> Result<Json<ListingHistoryResponse>, AppError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - chrono::Duration::days(7));
+    let region = Region::resolve(query.region, query.world_id)?;
+
+    let start = Instant::now();
+    let uploads = sqlx::query!(
+        "SELECT id, upload_time, world_id FROM upload
+        WHERE item_id = $1 AND upload_type = 0 AND region = $2 AND ($3::INT IS NULL OR world_id = $3)
+        AND upload_time >= $4 AND upload_time <= $5
+        ORDER BY upload_time DESC
+        LIMIT 100",
+        item_id,
+        region.as_str(),
+        query.world_id,
+        from,
+        to
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let upload_ids: Vec<Uuid> = uploads.iter().map(|x| x.id).collect();
+    let listings = sqlx::query_as!(
+        Listing,
+        r#"SELECT h.upload_id, h.world_id, h.item_id, h.hq, h.seller_id, h.retainer_id, h.retainer_name,
+        h.creator_id, h.creator_name, h.last_review_time, h.price_per_unit, h.quantity,
+        h.retainer_city_id, h.materia_count, h.region, w.name as "world_name?",
+        COALESCE((
+            SELECT json_agg(json_build_object('slot_id', m.slot_id, 'materia_id', m.materia_id) ORDER BY m.slot_id)
+            FROM listing_materia m WHERE m.upload_id = h.upload_id AND m.listing_index = h.listing_index
+        ), '[]') as "materia!: sqlx::types::Json<Vec<ListingMateria>>"
+        FROM listing_history h
+        LEFT JOIN world w ON w.world_id = h.world_id
+        WHERE h.upload_id = ANY($1) ORDER BY h.price_per_unit ASC"#,
+        &upload_ids
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_listings_history");
+
+    let mut by_upload: HashMap<Uuid, Vec<Listing>> = HashMap::new();
+    for listing in listings {
+        by_upload
+            .entry(listing.upload_id)
+            .or_default()
+            .push(listing);
+    }
+
+    // uploads with no listings are kept, they mean the market board was empty.
+    let snapshots = uploads
+        .into_iter()
+        .map(|upload| ListingSnapshot {
+            listings: by_upload.remove(&upload.id).unwrap_or_default(),
+            upload_id: upload.id,
+            upload_time: upload.upload_time,
+            world_id: upload.world_id,
+        })
+        .collect();
+
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+    Ok(Json(ListingHistoryResponse { item, snapshots }))
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct PurchasesResponse {
     pub item: ItemInfo,
     pub page: i64,
     pub purchases: Vec<Purchase>,
+    /// Pass as `cursor` to get the next page, only set for full pages sorted by `purchase_time`.
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+/// Position of the last purchase of a page, purchases are ordered by these columns descending.
+/// With the item they are the natural key of a purchase, so no purchase is skipped between pages.
+#[derive(Debug, Serialize, Deserialize)]
+struct PurchaseCursor {
+    purchase_time: DateTime<Utc>,
+    world_id: i32,
+    buyer_name: String,
+    price_per_unit: i32,
+    quantity: i32,
+    hq: bool,
+}
+
+impl PurchaseCursor {
+    fn next(purchases: &[Purchase]) -> Option<String> {
+        // a short page is the last one.
+        if Ok(purchases.len()) != usize::try_from(PURCHASES_PAGE_SIZE) {
+            return None;
+        }
+
+        purchases.last().map(|last| {
+            cursor::encode(&Self {
+                purchase_time: last.purchase_time,
+                world_id: last.world_id,
+                buyer_name: last.buyer_name.clone(),
+                price_per_unit: last.price_per_unit,
+                quantity: last.quantity,
+                hq: last.hq,
+            })
+        })
+    }
+}
+
+const PURCHASES_PAGE_SIZE: i64 = 250;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseSort {
+    /// Cheapest unit price first.
+    Price,
+    /// Cheapest purchase (price * quantity) first.
+    Total,
+    /// Most recent first.
+    PurchaseTime,
+}
+
+/// Filters applied in the purchases query, when all are empty page 0 is cached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PurchaseFilters {
+    hq: Option<bool>,
+    min_quantity: Option<i32>,
+    max_price: Option<i32>,
+    sort: Option<PurchaseSort>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
 pub struct PurchasesQuery {
+    #[validate(range(min = 0))]
     pub page: Option<i64>,
+    /// `next_cursor` of the previous page, takes precedence over `page`.
+    pub cursor: Option<String>,
+    pub region: Option<Region>,
+    pub hq: Option<bool>,
+    pub min_quantity: Option<i32>,
+    pub max_price: Option<i32>,
+    /// Defaults to `purchase_time`.
+    pub sort: Option<PurchaseSort>,
+}
+
+impl PurchasesQuery {
+    const fn filters(&self) -> PurchaseFilters {
+        PurchaseFilters {
+            hq: self.hq,
+            min_quantity: self.min_quantity,
+            max_price: self.max_price,
+            sort: self.sort,
+        }
+    }
+}
+
+async fn fetch_purchases(
+    item_id: i32,
+    region: Region,
+    offset: i64,
+    after: Option<&PurchaseCursor>,
+    filters: PurchaseFilters,
+    db: &PgPool,
+) -> Result<Vec<Purchase>, sqlx::Error> {
+    let sort = match filters.sort.unwrap_or(PurchaseSort::PurchaseTime) {
+        PurchaseSort::Price => "price",
+        PurchaseSort::Total => "total",
+        PurchaseSort::PurchaseTime => "purchase_time",
+    };
+
+    let start = Instant::now();
+    let purchases = sqlx::query_as!(
+        Purchase,
+        r#"SELECT p.*, w.name as "world_name?" FROM purchase p
+        LEFT JOIN world w ON w.world_id = p.world_id
+        WHERE p.item_id = $1 AND p.region = $4
+        AND ($5::BOOL IS NULL OR p.hq = $5)
+        AND ($6::INT IS NULL OR p.quantity >= $6)
+        AND ($7::INT IS NULL OR p.price_per_unit <= $7)
+        AND ($9::TIMESTAMPTZ IS NULL OR (p.purchase_time, p.world_id, p.buyer_name, p.price_per_unit, p.quantity, p.hq)
+            < ($9, $10, $11, $12, $13, $14))
+        ORDER BY
+            CASE WHEN $8 = 'price' THEN p.price_per_unit END ASC,
+            CASE WHEN $8 = 'total' THEN p.price_per_unit::BIGINT * p.quantity END ASC,
+            p.purchase_time DESC, p.world_id DESC, p.buyer_name DESC,
+            p.price_per_unit DESC, p.quantity DESC, p.hq DESC
+        OFFSET $2 LIMIT $3"#,
+        item_id,
+        if after.is_some() { 0 } else { offset },
+        PURCHASES_PAGE_SIZE,
+        region.as_str(),
+        filters.hq,
+        filters.min_quantity,
+        filters.max_price,
+        sort,
+        after.map(|x| x.purchase_time),
+        after.map(|x| x.world_id),
+        after.map(|x| x.buyer_name.as_str()),
+        after.map(|x| x.price_per_unit),
+        after.map(|x| x.quantity),
+        after.map(|x| x.hq),
+    )
+    .fetch_all(db)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "purchases");
+
+    Ok(purchases)
 }
 
 pub async fn purchases(
     State(state): State<AppState>,
     Path(item_id): Path<i32>,
     Query(query): Query<PurchasesQuery>,
+    lang: Language,
 ) -> Result<Json<PurchasesResponse>, AppError> {
+    query.validate()?;
+
     let page = query.page.unwrap_or(0);
+    let offset = page
+        .checked_mul(PURCHASES_PAGE_SIZE)
+        .ok_or_else(|| AppError::BadRequest(format!("page {page} is too large")))?;
+    let region = query.region.unwrap_or_default();
+    let filters = query.filters();
     increment_counter!("xivhub_purchases_request", "page" => page.to_string());
 
-    // only cache page 0.
-    let purchases = if page == 0 {
-        state.item_purchase_cache.try_get_with(item_id, async {
-            increment_counter!("xivhub_purchases_request_cache_miss", "page" => page.to_string());
-
-            let start = Instant::now();
-            let purchases = sqlx::query_as!(
-                    Purchase,
-                    "SELECT * FROM purchase WHERE item_id = $1 ORDER BY purchase_time DESC OFFSET $2 LIMIT $3",
-                    item_id,
-                    page * 250,
-                    250
-            )
-            .fetch_all(&state.pool)
-            .await?;
-            let elapsed = start.elapsed();
-            histogram!("xivhub_query", elapsed, "type" => "purchases");
-
-            let item = fetch_item_info(item_id, &state.pool).await?;
-            Ok::<_, sqlx::Error>(PurchasesResponse {
-                item,
-                page,
-                purchases,
+    // cursors only exist for the default ordering.
+    let keyset = matches!(filters.sort, None | Some(PurchaseSort::PurchaseTime));
+    let after = match (&query.cursor, keyset) {
+        (Some(x), true) => Some(cursor::decode::<PurchaseCursor>(x)?),
+        (Some(_), false) => {
+            return Err(AppError::BadRequest(
+                "cursor requires sort=purchase_time".to_string(),
+            ))
+        }
+        (None, _) => None,
+    };
+
+    // only cache the unfiltered first page of the global region.
+    let purchases = if page == 0
+        && after.is_none()
+        && region == Region::Global
+        && filters == PurchaseFilters::default()
+    {
+        state
+            .item_purchase_cache
+            .try_get_with((item_id, lang), async {
+                increment_counter!("xivhub_purchases_request_cache_miss", "page" => page.to_string());
+
+                let purchases =
+                    fetch_purchases(item_id, region, 0, None, filters, &state.pool).await?;
+                let item = fetch_item_info(item_id, lang, &state.pool).await?;
+                Ok::<_, sqlx::Error>(PurchasesResponse {
+                    item,
+                    page,
+                    next_cursor: PurchaseCursor::next(&purchases),
+                    purchases,
+                })
             })
-        }).await?
+            .await?
     } else {
         increment_counter!("xivhub_purchases_request_cache_miss", "page" => page.to_string());
 
-        let start = Instant::now();
-        let purchases = sqlx::query_as!(
-            Purchase,
-            "SELECT * FROM purchase WHERE item_id = $1 ORDER BY purchase_time DESC OFFSET $2 LIMIT $3",
+        let purchases = fetch_purchases(
             item_id,
-            page * 250,
-            250
+            region,
+            offset,
+            after.as_ref(),
+            filters,
+            &state.pool,
         )
-        .fetch_all(&state.pool)
         .await?;
-        let elapsed = start.elapsed();
-        histogram!("xivhub_query", elapsed, "type" => "purchases");
-
-        let item = fetch_item_info(item_id, &state.pool).await?;
+        let item = fetch_item_info(item_id, lang, &state.pool).await?;
         PurchasesResponse {
             item,
             page,
+            next_cursor: if keyset {
+                PurchaseCursor::next(&purchases)
+            } else {
+                None
+            },
             purchases,
         }
     };
@@ -141,32 +583,42 @@ pub struct RangePurchases {
     pub time: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct PurchasesByDayQuery {
+    pub region: Option<Region>,
+}
+
 pub async fn purchases_by_day(
     State(state): State<AppState>,
     Path(item_id): Path<i32>,
+    Query(query): Query<PurchasesByDayQuery>,
+    lang: Language,
 ) -> Result<Json<DayPurchasesResponse>, AppError> {
+    let region = query.region.unwrap_or_default();
+
     let start = Instant::now();
     let purchases = sqlx::query_as!(
         RangePurchases,
         "SELECT * FROM (
             SELECT
-            date_trunc('day', purchase_time) as time,
-            MAX(price_per_unit) as high,
-            MIN(price_per_unit) as low,
-            CAST(AVG(price_per_unit) AS INTEGER) as average,
-            SUM(quantity) as quantity
-            FROM purchase WHERE item_id = $1
-            GROUP BY date_trunc('day', purchase_time)
-            ORDER BY time DESC LIMIT 30
+            day::TIMESTAMP AT TIME ZONE 'UTC' as time,
+            MAX(high) as high,
+            MIN(low) as low,
+            CAST(SUM(price_sum) / SUM(trades) AS INTEGER) as average,
+            CAST(SUM(quantity) AS BIGINT) as quantity
+            FROM purchase_daily WHERE item_id = $1 AND region = $2
+            GROUP BY day
+            ORDER BY day DESC LIMIT 30
         ) AS T1 ORDER BY time ASC",
         item_id,
+        region.as_str(),
     )
     .fetch_all(&state.pool)
     .await?;
     let elapsed = start.elapsed();
     histogram!("xivhub_query", elapsed, "type" => "item_purchases_by_day");
 
-    let item = fetch_item_info(item_id, &state.pool).await?;
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
     let purchases = DayPurchasesResponse {
         item,
         days: purchases,
@@ -175,6 +627,381 @@ pub async fn purchases_by_day(
     Ok(Json(purchases))
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Bucket {
+    /// The `date_trunc` field.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    /// The range when `from` is not given.
+    const fn default_range(self) -> chrono::Duration {
+        match self {
+            Self::Hour => chrono::Duration::days(2),
+            Self::Day => chrono::Duration::days(30),
+            Self::Week => chrono::Duration::weeks(26),
+        }
+    }
+
+    /// The longest range that can be requested.
+    const fn max_range(self) -> chrono::Duration {
+        match self {
+            Self::Hour => chrono::Duration::days(14),
+            Self::Day => chrono::Duration::days(366),
+            Self::Week => chrono::Duration::weeks(156),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OhlcQuery {
+    /// Defaults to day.
+    pub bucket: Option<Bucket>,
+    /// Defaults to `to` minus the default range of the bucket.
+    pub from: Option<DateTime<Utc>>,
+    /// Defaults to now.
+    pub to: Option<DateTime<Utc>>,
+    /// Only use purchases of this world.
+    pub world: Option<i32>,
+    pub region: Option<Region>,
+    pub hq: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OhlcResponse {
+    pub item: ItemInfo,
+    pub bucket: &'static str,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Oldest first, buckets without purchases are left out.
+    pub candles: Vec<Candle>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Candle {
+    /// The start of the bucket.
+    pub time: DateTime<Utc>,
+    pub open: i32,
+    pub high: i32,
+    pub low: i32,
+    pub close: i32,
+    /// Units sold.
+    pub volume: i64,
+    pub trades: i64,
+}
+
+/// returns open, high, low and close unit prices of an item per hour, day or week
+pub async fn purchases_ohlc(
+    State(state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Query(query): Query<OhlcQuery>,
+    lang: Language,
+) -> Result<Json<OhlcResponse>, AppError> {
+    let bucket = query.bucket.unwrap_or(Bucket::Day);
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - bucket.default_range());
+    if from >= to {
+        return Err(AppError::BadRequest("from must be before to".to_string()));
+    }
+    if to - from > bucket.max_range() {
+        return Err(AppError::BadRequest(format!(
+            "the range is longer than {} days for {} buckets",
+            bucket.max_range().num_days(),
+            bucket.as_str()
+        )));
+    }
+    let region = Region::resolve(query.region, query.world)?;
+
+    let start = Instant::now();
+    let candles = sqlx::query_as!(
+        Candle,
+        r#"SELECT date_trunc($2, purchase_time) as "time!",
+        (array_agg(price_per_unit ORDER BY purchase_time ASC))[1] as "open!",
+        MAX(price_per_unit) as "high!",
+        MIN(price_per_unit) as "low!",
+        (array_agg(price_per_unit ORDER BY purchase_time DESC))[1] as "close!",
+        SUM(quantity) as "volume!",
+        COUNT(*) as "trades!"
+        FROM purchase
+        WHERE item_id = $1 AND region = $3 AND ($4::INT IS NULL OR world_id = $4)
+        AND ($5::BOOL IS NULL OR hq = $5)
+        AND purchase_time >= $6 AND purchase_time < $7
+        GROUP BY 1
+        ORDER BY 1 ASC"#,
+        item_id,
+        bucket.as_str(),
+        region.as_str(),
+        query.world,
+        query.hq,
+        from,
+        to
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_purchases_ohlc");
+
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+    Ok(Json(OhlcResponse {
+        item,
+        bucket: bucket.as_str(),
+        from,
+        to,
+        candles,
+    }))
+}
+
+/// The longest `window` of `price_stats`, purchases are purged after 30 days by default.
+const MAX_PRICE_STATS_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize)]
+pub struct PriceStatsQuery {
+    /// `12h`, `7d`, `2w`... Defaults to 7 days.
+    pub window: Option<String>,
+    /// Only use purchases of this world.
+    pub world: Option<i32>,
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceStatsResponse {
+    pub item: ItemInfo,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub nq: PriceStats,
+    pub hq: PriceStats,
+}
+
+/// Unit price statistics of the purchases in the window, the prices are None without purchases.
+#[derive(Debug, Serialize, Default)]
+pub struct PriceStats {
+    pub sales: i64,
+    pub units: i64,
+    pub median: Option<f64>,
+    pub p10: Option<f64>,
+    pub p25: Option<f64>,
+    pub p75: Option<f64>,
+    pub p90: Option<f64>,
+    /// Volume-weighted average price.
+    pub vwap: Option<f64>,
+    /// Units sold per day.
+    pub velocity: f64,
+}
+
+/// returns price percentiles, vwap and sale velocity of an item, nq and hq apart
+pub async fn price_stats(
+    State(state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Query(query): Query<PriceStatsQuery>,
+    lang: Language,
+) -> Result<Json<PriceStatsResponse>, AppError> {
+    let window = match &query.window {
+        Some(window) => parse_duration(window)
+            .ok_or_else(|| AppError::BadRequest(format!("invalid window: {window}")))?,
+        None => chrono::Duration::days(7),
+    };
+    if window > chrono::Duration::days(MAX_PRICE_STATS_WINDOW_DAYS) {
+        return Err(AppError::BadRequest(format!(
+            "window is longer than {MAX_PRICE_STATS_WINDOW_DAYS} days"
+        )));
+    }
+    let region = Region::resolve(query.region, query.world)?;
+    let to = Utc::now();
+    let from = to - window;
+
+    let start = Instant::now();
+    let rows = sqlx::query!(
+        r#"SELECT hq, COUNT(*) as "sales!", SUM(quantity) as "units!",
+        percentile_cont(ARRAY[0.1, 0.25, 0.5, 0.75, 0.9]) WITHIN GROUP (ORDER BY price_per_unit) as "percentiles!",
+        (SUM(price_per_unit::FLOAT8 * quantity) / NULLIF(SUM(quantity), 0)) as vwap
+        FROM purchase
+        WHERE item_id = $1 AND region = $2 AND ($3::INT IS NULL OR world_id = $3)
+        AND purchase_time > $4
+        GROUP BY hq"#,
+        item_id,
+        region.as_str(),
+        query.world,
+        from
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_price_stats");
+
+    let mut nq = PriceStats::default();
+    let mut hq = PriceStats::default();
+    #[allow(clippy::cast_precision_loss)]
+    let days = window.num_seconds() as f64 / 86400.0;
+    for row in rows {
+        let percentile = |i: usize| row.percentiles.get(i).copied();
+        #[allow(clippy::cast_precision_loss)]
+        let prices = PriceStats {
+            sales: row.sales,
+            units: row.units,
+            median: percentile(2),
+            p10: percentile(0),
+            p25: percentile(1),
+            p75: percentile(3),
+            p90: percentile(4),
+            vwap: row.vwap,
+            velocity: row.units as f64 / days,
+        };
+        if row.hq {
+            hq = prices;
+        } else {
+            nq = prices;
+        }
+    }
+
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+    Ok(Json(PriceStatsResponse {
+        item,
+        from,
+        to,
+        nq,
+        hq,
+    }))
+}
+
+/// The longest range of `trends`.
+const MAX_TRENDS_YEARS: i32 = 10;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendBucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl TrendBucket {
+    /// The `date_trunc` field.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendsQuery {
+    /// Defaults to week.
+    pub bucket: Option<TrendBucket>,
+    /// Defaults to a year before `to`.
+    pub from: Option<NaiveDate>,
+    /// Defaults to today.
+    pub to: Option<NaiveDate>,
+    /// Only use purchases of this world.
+    pub world: Option<i32>,
+    pub region: Option<Region>,
+    pub hq: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrendsResponse {
+    pub item: ItemInfo,
+    pub bucket: &'static str,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Oldest first, buckets without purchases are left out.
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrendPoint {
+    /// The first day of the bucket.
+    pub day: NaiveDate,
+    pub high: i32,
+    pub low: i32,
+    /// Average unit price of the trades.
+    pub average: f64,
+    /// Volume-weighted average price, None when the trades have no quantity.
+    pub vwap: Option<f64>,
+    /// Units sold.
+    pub volume: i64,
+    pub trades: i64,
+}
+
+/// returns long-term prices and volume of an item from the daily rollups, they outlive the purchases
+pub async fn trends(
+    State(state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Query(query): Query<TrendsQuery>,
+    lang: Language,
+) -> Result<Json<TrendsResponse>, AppError> {
+    let bucket = query.bucket.unwrap_or(TrendBucket::Week);
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query
+        .from
+        .or_else(|| to.checked_sub_months(chrono::Months::new(12)))
+        .unwrap_or(to);
+    if from > to {
+        return Err(AppError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+    if to.years_since(from).unwrap_or(0) >= MAX_TRENDS_YEARS.unsigned_abs() {
+        return Err(AppError::BadRequest(format!(
+            "the range is longer than {MAX_TRENDS_YEARS} years"
+        )));
+    }
+    let region = Region::resolve(query.region, query.world)?;
+
+    let start = Instant::now();
+    let points = sqlx::query_as!(
+        TrendPoint,
+        r#"SELECT date_trunc($2, day)::DATE as "day!",
+        MAX(high) as "high!",
+        MIN(low) as "low!",
+        (SUM(price_sum)::FLOAT8 / SUM(trades)) as "average!",
+        (SUM(turnover)::FLOAT8 / NULLIF(SUM(quantity), 0)) as vwap,
+        CAST(SUM(quantity) AS BIGINT) as "volume!",
+        CAST(SUM(trades) AS BIGINT) as "trades!"
+        FROM purchase_daily
+        WHERE item_id = $1 AND region = $3 AND ($4::INT IS NULL OR world_id = $4)
+        AND ($5::BOOL IS NULL OR hq = $5)
+        AND day >= $6 AND day <= $7
+        GROUP BY 1
+        ORDER BY 1 ASC"#,
+        item_id,
+        bucket.as_str(),
+        region.as_str(),
+        query.world,
+        query.hq,
+        from,
+        to
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_trends");
+
+    let item = fetch_item_info(item_id, lang, &state.pool).await?;
+
+    Ok(Json(TrendsResponse {
+        item,
+        bucket: bucket.as_str(),
+        from,
+        to,
+        points,
+    }))
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ItemUploadDates {
     pub world_id: i32,
@@ -200,10 +1027,35 @@ pub async fn get_item_upload_dates(
     Ok(Json(uploads))
 }
 
-#[derive(Debug, Deserialize)]
+const DEFAULT_ITEMS_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct ItemListQuery {
+    #[validate(range(min = 0))]
     pub page: Option<i64>,
+    /// Defaults to 100.
+    #[validate(range(min = 1, max = 500))]
+    pub page_size: Option<i64>,
+    /// `next_cursor` of the previous page, takes precedence over `page`.
+    pub cursor: Option<String>,
+    /// Fuzzy match on the name and full text match on the description, results are sorted by relevance.
     pub search: Option<String>,
+    pub item_search_category: Option<i32>,
+    pub item_kind_id: Option<i32>,
+    pub min_level_item: Option<i32>,
+    pub max_level_item: Option<i32>,
+    pub min_level_equip: Option<i32>,
+    pub max_level_equip: Option<i32>,
+    pub rarity: Option<i32>,
+    pub can_be_hq: Option<bool>,
+}
+
+/// Position of the last item of a page, items are ordered by relevance then id.
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemCursor {
+    item_id: i32,
+    #[serde(default)]
+    relevance: f32,
 }
 
 #[derive(Debug, Serialize)]
@@ -225,80 +1077,255 @@ pub struct ItemList {
     pub rarity: i32,
     pub can_be_hq: bool,
     pub listings: Option<i64>,
+    /// Search relevance, 0 without search.
+    #[serde(skip)]
+    pub relevance: Option<f32>,
 }
 
+/// Paging info of a list response.
 #[derive(Debug, Serialize)]
-pub struct ListItemsResponse {
-    pub items: Vec<ItemList>,
+pub struct Pagination {
     pub page: i64,
+    pub page_size: i64,
+    pub total_items: i64,
     pub total_pages: i64,
+    /// Whether a page follows this one.
+    pub has_more: bool,
+}
+
+impl Pagination {
+    #[must_use]
+    pub const fn new(page: i64, page_size: i64, total_items: i64, has_more: bool) -> Self {
+        Self {
+            page,
+            page_size,
+            total_items,
+            total_pages: (total_items + page_size - 1) / page_size,
+            has_more,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListItemsResponse {
+    pub items: Vec<ItemList>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// Pass as `cursor` to get the next page, unset on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Number of items matching the search and filters of `query`, ignoring the paging.
+async fn count_items(query: &ItemListQuery, db: &PgPool) -> Result<i64, sqlx::Error> {
+    let start = Instant::now();
+    let total_items = sqlx::query!(
+        "SELECT COUNT(*) from item_info
+        WHERE ($1::TEXT IS NULL OR item_id IN (
+            SELECT s.item_id FROM item_info_locale s
+            WHERE $1 <% s.name OR s.name ILIKE '%' || $10 || '%' ESCAPE '\\'
+            OR to_tsvector('simple', s.description) @@ plainto_tsquery('simple', $1)
+        ))
+        AND ($2::INT IS NULL OR item_search_category = $2)
+        AND ($3::INT IS NULL OR item_kind_id = $3)
+        AND ($4::INT IS NULL OR level_item >= $4)
+        AND ($5::INT IS NULL OR level_item <= $5)
+        AND ($6::INT IS NULL OR level_equip >= $6)
+        AND ($7::INT IS NULL OR level_equip <= $7)
+        AND ($8::INT IS NULL OR rarity = $8)
+        AND ($9::BOOL IS NULL OR can_be_hq = $9)",
+        query.search,
+        query.item_search_category,
+        query.item_kind_id,
+        query.min_level_item,
+        query.max_level_item,
+        query.min_level_equip,
+        query.max_level_equip,
+        query.rarity,
+        query.can_be_hq,
+        query.search.as_deref().map(escape_like),
+    )
+    .fetch_one(db)
+    .await?
+    .count
+    .unwrap_or(0);
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "list_items_total", "search" => query.search.is_some().to_string());
+
+    Ok(total_items)
 }
 
 pub async fn list(
     State(state): State<AppState>,
     Query(query): Query<ItemListQuery>,
+    lang: Language,
 ) -> Result<Json<ListItemsResponse>, AppError> {
+    query.validate()?;
+
     let page = query.page.unwrap_or(0);
+    let page_size = query.page_size.unwrap_or(DEFAULT_ITEMS_PAGE_SIZE);
+    let offset = page
+        .checked_mul(page_size)
+        .ok_or_else(|| AppError::BadRequest(format!("page {page} is too large")))?;
+    let after = query
+        .cursor
+        .as_deref()
+        .map(cursor::decode::<ItemCursor>)
+        .transpose()?;
 
-    let start = Instant::now();
-    let total_items = {
-        if let Some(search) = &query.search {
-            sqlx::query!(
-                "SELECT COUNT(*) from item_info WHERE LOWER(name) LIKE LOWER($1)",
-                format!("%{search}%")
-            )
-            .fetch_one(&state.pool)
-            .await?
-            .count
-        } else {
-            sqlx::query!("SELECT COUNT(*) from item_info")
-                .fetch_one(&state.pool)
-                .await?
-                .count
-        }
-    }
-    .unwrap_or(0);
-    let elapsed = start.elapsed();
-    histogram!("xivhub_query", elapsed, "type" => "list_items_total", "search" => query.search.is_some().to_string());
+    let total_items = count_items(&query, &state.pool).await?;
 
+    // one extra row tells whether there is a next page.
     let start = Instant::now();
-    let items = {
-        if let Some(search) = &query.search {
-            sqlx::query_as!(
-                ItemList,
-                "SELECT i.*, (SELECT COUNT(*) FROM listing l WHERE l.item_id = i.item_id) as listings
-                FROM item_info i
-                WHERE LOWER(name) LIKE LOWER($1)
-                ORDER BY item_id ASC
-                OFFSET $2
-                LIMIT 100
-                ",
-                format!("%{search}%"),
-                page * 100
-            )
-            .fetch_all(&state.pool)
-            .await?
-        } else {
-            sqlx::query_as!(
-                ItemList,
-                "SELECT i.*, (SELECT COUNT(*) FROM listing l WHERE l.item_id = i.item_id) as listings
-                FROM item_info i
-                ORDER BY item_id ASC
-                OFFSET $1
-                LIMIT 100
-                ",
-                page * 50
-            )
-            .fetch_all(&state.pool)
-            .await?
-        }
-    };
+    let mut items = sqlx::query_as!(
+        ItemList,
+        r#"SELECT i.item_id, COALESCE(t.name, i.name) as "name!", i.icon, i.icon_hd,
+        COALESCE(t.description, i.description) as "description!", i.item_kind_name, i.item_kind_id,
+        i.item_search_category, i.item_search_category_iconhd,
+        COALESCE(t.item_search_category_name, i.item_search_category_name) as "item_search_category_name!",
+        i.stack_size, i.level_item, i.level_equip, i.materia_slot_count, i.rarity, i.can_be_hq,
+        (SELECT COUNT(*) FROM listing l WHERE l.item_id = i.item_id) as listings, i.relevance
+        FROM (
+            -- the best match of any language decides the relevance.
+            SELECT *, CASE WHEN $1::TEXT IS NULL THEN 0 ELSE (
+                SELECT MAX(
+                    word_similarity($1, s.name) + similarity($1, s.name)
+                    + ts_rank(to_tsvector('simple', s.description), plainto_tsquery('simple', $1))
+                ) FROM item_info_locale s WHERE s.item_id = item_info.item_id
+            ) END::REAL as relevance
+            FROM item_info
+            WHERE ($1::TEXT IS NULL OR item_id IN (
+                SELECT s.item_id FROM item_info_locale s
+                WHERE $1 <% s.name OR s.name ILIKE '%' || $15 || '%' ESCAPE '\'
+                OR to_tsvector('simple', s.description) @@ plainto_tsquery('simple', $1)
+            ))
+            AND ($6::INT IS NULL OR item_search_category = $6)
+            AND ($7::INT IS NULL OR item_kind_id = $7)
+            AND ($8::INT IS NULL OR level_item >= $8)
+            AND ($9::INT IS NULL OR level_item <= $9)
+            AND ($10::INT IS NULL OR level_equip >= $10)
+            AND ($11::INT IS NULL OR level_equip <= $11)
+            AND ($12::INT IS NULL OR rarity = $12)
+            AND ($13::BOOL IS NULL OR can_be_hq = $13)
+        ) i
+        LEFT JOIN item_info_locale t ON t.item_id = i.item_id AND t.lang = $14
+        WHERE ($2::INT IS NULL OR (i.relevance, -i.item_id) < ($5::REAL, -$2))
+        ORDER BY i.relevance DESC, i.item_id ASC
+        OFFSET $3
+        LIMIT $4
+        "#,
+        query.search,
+        after.as_ref().map(|x| x.item_id),
+        if after.is_some() { 0 } else { offset },
+        page_size + 1,
+        after.as_ref().map(|x| x.relevance),
+        query.item_search_category,
+        query.item_kind_id,
+        query.min_level_item,
+        query.max_level_item,
+        query.min_level_equip,
+        query.max_level_equip,
+        query.rarity,
+        query.can_be_hq,
+        lang.as_str(),
+        query.search.as_deref().map(escape_like),
+    )
+    .fetch_all(&state.pool)
+    .await?;
     let elapsed = start.elapsed();
     histogram!("xivhub_query", elapsed, "type" => "list_item", "search" => query.search.is_some().to_string());
 
+    let has_more = items.len() > usize::try_from(page_size)?;
+    items.truncate(usize::try_from(page_size)?);
+
+    let next_cursor = if has_more {
+        items.last().map(|x| {
+            cursor::encode(&ItemCursor {
+                item_id: x.item_id,
+                relevance: x.relevance.unwrap_or_default(),
+            })
+        })
+    } else {
+        None
+    };
+
     Ok(Json(ListItemsResponse {
         items,
-        page,
-        total_pages: total_items / 50,
+        pagination: Pagination::new(page, page_size, total_items, has_more),
+        next_cursor,
     }))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct AutocompleteQuery {
+    pub q: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemSuggestion {
+    pub item_id: i32,
+    pub name: String,
+    pub icon: String,
+}
+
+/// returns up to 10 items whose name, or a word of it, starts with `q`
+pub async fn autocomplete(
+    State(state): State<AppState>,
+    Query(query): Query<AutocompleteQuery>,
+    lang: Language,
+) -> Result<Json<Vec<ItemSuggestion>>, AppError> {
+    if query.q.trim().is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let start = Instant::now();
+    let items = sqlx::query_as!(
+        ItemSuggestion,
+        "SELECT t.item_id, t.name, i.icon FROM item_info_locale t
+        JOIN item_info i ON i.item_id = t.item_id
+        WHERE t.lang = $2 AND (t.name ILIKE $1 || '%' ESCAPE '\\' OR t.name ILIKE '% ' || $1 || '%' ESCAPE '\\')
+        ORDER BY t.name ILIKE $1 || '%' ESCAPE '\\' DESC, LENGTH(t.name), t.name
+        LIMIT 10",
+        escape_like(query.q.trim()),
+        lang.as_str()
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "item_autocomplete");
+
+    Ok(Json(items))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Category {
+    pub item_search_category: i32,
+    pub name: String,
+    pub icon_hd: String,
+    pub item_count: i64,
+}
+
+/// returns the market board search categories with how many items they hold
+pub async fn categories(
+    State(state): State<AppState>,
+    lang: Language,
+) -> Result<Json<Vec<Category>>, AppError> {
+    let start = Instant::now();
+    let categories = sqlx::query_as!(
+        Category,
+        r#"SELECT i.item_search_category,
+        MAX(COALESCE(t.item_search_category_name, i.item_search_category_name)) as "name!",
+        i.item_search_category_iconhd as icon_hd, COUNT(*) as "item_count!"
+        FROM item_info i
+        LEFT JOIN item_info_locale t ON t.item_id = i.item_id AND t.lang = $1
+        WHERE i.item_search_category > 0
+        GROUP BY i.item_search_category, i.item_search_category_iconhd
+        ORDER BY i.item_search_category"#,
+        lang.as_str()
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "categories");
+
+    Ok(Json(categories))
+}
@@ -1,3 +1,6 @@
+pub mod craft;
 pub mod item;
 pub mod stats;
 pub mod upload;
+pub mod vendor;
+pub mod world;
@@ -41,8 +41,8 @@ pub enum FlattenError {
 async fn flatten<T: Send>(handle: JoinHandle<Result<T, sqlx::Error>>) -> Result<T, AppError> {
     match handle.await {
         Ok(Ok(result)) => Ok(result),
-        Ok(Err(err)) => Err(AppError(eyre!("db error: {:?}", err))),
-        Err(err) => Err(AppError(eyre!("join error: {:?}", err))),
+        Ok(Err(err)) => Err(AppError::Internal(eyre!("db error: {:?}", err))),
+        Err(err) => Err(AppError::Internal(eyre!("join error: {:?}", err))),
     }
 }
 
@@ -103,7 +103,7 @@ pub async fn stats(State(state): State<AppState>) -> Result<Json<Stats>, AppErro
     let uploads_per_day = tokio::spawn(async move {
         let start = Instant::now();
         let q = sqlx::query_as!(DayCount,
-            "SELECT COUNT(*) as count, date(timezone('UTC', upload_time)) as day from upload GROUP BY date(timezone('UTC', upload_time)) ORDER BY day DESC LIMIT 15")
+            r#"SELECT uploads as "count?", day as "day?" from upload_daily ORDER BY day DESC LIMIT 15"#)
             .fetch_all(&pool).await;
         let elapsed = start.elapsed();
         histogram!("xivhub_query", elapsed, "type" => "stats_uploads_per_day_count");
@@ -114,7 +114,7 @@ pub async fn stats(State(state): State<AppState>) -> Result<Json<Stats>, AppErro
     let purchase_by_day = tokio::spawn(async move {
         let start = Instant::now();
         let q = sqlx::query_as!(DayCount,
-            "SELECT COUNT(*) as count, date(timezone('UTC', purchase_time)) as day from purchase GROUP BY date(timezone('UTC', purchase_time)) ORDER BY day DESC LIMIT 15")
+            r#"SELECT CAST(SUM(trades) AS BIGINT) as count, day as "day?" from purchase_daily GROUP BY day ORDER BY day DESC LIMIT 15"#)
             .fetch_all(&pool).await;
         let elapsed = start.elapsed();
         histogram!("xivhub_query", elapsed, "type" => "stats_purchases_by_day_count");
@@ -1,25 +1,34 @@
 use crate::entities::Upload;
-use axum::{extract::State, Json};
-use axum_prometheus::metrics::{histogram, increment_counter};
-use chrono::TimeZone;
-use color_eyre::eyre::eyre;
+use axum::extract::State;
+use axum_prometheus::metrics::{counter, histogram, increment_counter};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::Deserialize;
+use sqlx::{PgPool, Postgres, Transaction};
 use std::time::Instant;
 use tracing::info;
 use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
 
-use crate::{error::AppError, AppState};
+use crate::{
+    error::AppError,
+    extractor::{Json, Query},
+    world::Region,
+    AppState,
+};
 
-#[derive(Debug, Deserialize)]
-pub struct Request<T> {
+#[derive(Debug, Deserialize, Validate)]
+pub struct Request<T: Validate> {
     pub world_id: i32,
+    #[validate(range(min = 1))]
     pub item_id: i32,
+    #[validate(length(min = 1))]
     pub uploader_id: String,
+    #[validate]
     pub listings: Vec<T>,
 }
 
 #[allow(unused)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct RequestListing {
     pub hq: bool,
     pub seller_id: String,
@@ -28,30 +37,198 @@ pub struct RequestListing {
     pub creator_id: String, // artisan
     pub creator_name: String,
     pub on_mannequin: bool,
+    #[validate(custom = "not_in_future")]
     pub last_review_time: i64,
+    #[validate(range(min = 1))]
     pub price_per_unit: i32,
+    #[validate(range(min = 1))]
     pub quantity: i32,
     pub retainer_city: i32,
     pub materia: Vec<ItemMateria>,
 }
 
-#[allow(unused)]
 #[derive(Debug, Deserialize)]
 pub struct ItemMateria {
     pub slot_id: i32,
     pub materia_id: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct HistoryRequestListing {
     pub hq: bool,
     pub buyer_name: String,
     pub on_mannequin: bool,
+    #[validate(custom = "not_in_future")]
     pub purchase_time: i64,
+    #[validate(range(min = 1))]
     pub price_per_unit: i32,
+    #[validate(range(min = 1))]
     pub quantity: i32,
 }
 
+/// Leeway given to uploader clocks running ahead, in seconds.
+const MAX_CLOCK_SKEW: i64 = 5 * 60;
+
+fn not_in_future(timestamp: i64) -> Result<(), ValidationError> {
+    if timestamp > Utc::now().timestamp() + MAX_CLOCK_SKEW {
+        return Err(ValidationError::new("in_future"));
+    }
+    Ok(())
+}
+
+/// Runs the declarative validations, then checks the item and world exist.
+/// Returns the region the upload belongs to.
+async fn validate_upload<T: Validate + Sync>(
+    payload: &Request<T>,
+    db: &PgPool,
+) -> Result<Region, AppError> {
+    payload.validate()?;
+
+    let known = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM item_info WHERE item_id = $1) as "item!",
+        EXISTS(SELECT 1 FROM world) as "worlds_imported!",
+        (SELECT d.region_id FROM world w JOIN data_center d ON d.data_center_id = w.data_center_id
+            WHERE w.world_id = $2) as region_id"#,
+        payload.item_id,
+        payload.world_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    // the id ranges are only used until the worlds are imported.
+    let region = if known.worlds_imported {
+        known.region_id.and_then(Region::of_game_region)
+    } else {
+        Region::of_world(payload.world_id)
+    };
+    let Some(region) = region else {
+        let mut errors = ValidationErrors::new();
+        errors.add("world_id", ValidationError::new("unknown_world"));
+        return Err(errors.into());
+    };
+
+    if !known.item {
+        let mut errors = ValidationErrors::new();
+        errors.add("item_id", ValidationError::new("unknown_item"));
+        return Err(errors.into());
+    }
+
+    Ok(region)
+}
+
+/// Listings of an upload split into columns, so they can be inserted with a single UNNEST.
+#[derive(Debug, Default)]
+struct ListingBatch {
+    seller_id: Vec<String>,
+    retainer_id: Vec<String>,
+    retainer_name: Vec<String>,
+    creator_id: Vec<String>,
+    creator_name: Vec<String>,
+    last_review_time: Vec<DateTime<Utc>>,
+    price_per_unit: Vec<i32>,
+    quantity: Vec<i32>,
+    retainer_city_id: Vec<i32>,
+    materia_count: Vec<i32>,
+    hq: Vec<bool>,
+    listing_index: Vec<i32>,
+    materia_listing_index: Vec<i32>,
+    materia_slot_id: Vec<i32>,
+    materia_id: Vec<i32>,
+}
+
+impl ListingBatch {
+    fn push(&mut self, listing_index: i32, listing: RequestListing) -> Result<(), AppError> {
+        let date = Utc
+            .timestamp_opt(listing.last_review_time, 0)
+            .single()
+            .ok_or_else(|| AppError::BadRequest("invalid last_review_time date".to_string()))?;
+
+        self.materia_count.push(listing.materia.len().try_into()?);
+        for materia in listing.materia {
+            self.materia_listing_index.push(listing_index);
+            self.materia_slot_id.push(materia.slot_id);
+            self.materia_id.push(materia.materia_id);
+        }
+
+        self.seller_id.push(listing.seller_id);
+        self.retainer_id.push(listing.retainer_id);
+        self.retainer_name.push(listing.retainer_name);
+        self.creator_id.push(listing.creator_id);
+        self.creator_name.push(listing.creator_name);
+        self.last_review_time.push(date);
+        self.price_per_unit.push(listing.price_per_unit);
+        self.quantity.push(listing.quantity);
+        self.retainer_city_id.push(listing.retainer_city);
+        self.hq.push(listing.hq);
+        self.listing_index.push(listing_index);
+
+        Ok(())
+    }
+
+    /// Inserts the listings and their materia, returns the inserted listing count.
+    async fn insert(
+        &self,
+        trans: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+        world_id: i32,
+        item_id: i32,
+        region: Region,
+    ) -> Result<u64, sqlx::Error> {
+        let rows_affected = sqlx::query!(
+            "INSERT INTO listing (
+                upload_id, world_id, item_id, seller_id,
+                retainer_id, retainer_name, creator_id, creator_name,
+                last_review_time, price_per_unit, quantity,
+                retainer_city_id, materia_count, hq, listing_index, region)
+            SELECT $1, $2, $3, *, $16 FROM UNNEST(
+                $4::TEXT[], $5::TEXT[], $6::TEXT[], $7::TEXT[], $8::TEXT[],
+                $9::TIMESTAMPTZ[], $10::INT[], $11::INT[],
+                $12::INT[], $13::INT[], $14::BOOL[], $15::INT[])
+            ",
+            id,
+            world_id,
+            item_id,
+            &self.seller_id,
+            &self.retainer_id,
+            &self.retainer_name,
+            &self.creator_id,
+            &self.creator_name,
+            &self.last_review_time,
+            &self.price_per_unit,
+            &self.quantity,
+            &self.retainer_city_id,
+            &self.materia_count,
+            &self.hq,
+            &self.listing_index,
+            region.as_str(),
+        )
+        .execute(&mut *trans)
+        .await?
+        .rows_affected();
+
+        sqlx::query!(
+            "INSERT INTO listing_materia (upload_id, listing_index, slot_id, materia_id)
+            SELECT $1, * FROM UNNEST($2::INT[], $3::INT[], $4::INT[])",
+            id,
+            &self.materia_listing_index,
+            &self.materia_slot_id,
+            &self.materia_id,
+        )
+        .execute(&mut *trans)
+        .await?;
+
+        Ok(rows_affected)
+    }
+
+    fn len(&self) -> u32 {
+        self.listing_index.len().try_into().unwrap_or(u32::MAX)
+    }
+
+    fn materia_len(&self) -> u32 {
+        self.materia_id.len().try_into().unwrap_or(u32::MAX)
+    }
+}
+
 pub async fn listings(
     State(state): State<AppState>,
     Json(payload): Json<Request<RequestListing>>,
@@ -60,79 +237,78 @@ pub async fn listings(
     let date = chrono::Utc::now();
     info!("Received upload for item {}", payload.item_id);
 
-    if payload.world_id > 1000 || payload.world_id == 0 {
-        // mostly chinese servers
-        // todo: handle better
-        return Ok(());
+    let region = validate_upload(&payload, &state.pool).await?;
+
+    let mut batch = ListingBatch::default();
+    for (listing_index, listing) in payload.listings.into_iter().enumerate() {
+        batch.push(listing_index.try_into()?, listing)?;
     }
 
     let upload_time = Instant::now();
 
+    let mut trans = state.pool.begin().await?;
+
     sqlx::query!(
-        "INSERT INTO upload (id, uploader_id, upload_time, world_id, item_id, upload_type)
-        VALUES ($1,$2,$3,$4,$5,$6)",
+        "INSERT INTO upload (id, uploader_id, upload_time, world_id, item_id, upload_type, region)
+        VALUES ($1,$2,$3,$4,$5,$6,$7)",
         id,
         payload.uploader_id,
         date,
         payload.world_id,
         payload.item_id,
-        0
+        0,
+        region.as_str()
     )
-    .execute(&state.pool)
+    .execute(&mut trans)
     .await?;
 
-    // for now, dont keep a history of previous listings.
+    // the listing table only holds the latest snapshot, previous ones live in listing_history.
     let mut rows_affected = sqlx::query!(
         "DELETE FROM listing WHERE item_id = $1 AND world_id =$2",
         payload.item_id,
         payload.world_id
     )
-    .execute(&state.pool)
+    .execute(&mut trans)
     .await?
     .rows_affected();
 
-    for listing in payload.listings {
-        let date = chrono::Utc
-            .timestamp_opt(listing.last_review_time, 0)
-            .single()
-            .ok_or_else(|| AppError(eyre!("invalid last_review_time date")))?;
+    rows_affected += batch
+        .insert(&mut trans, id, payload.world_id, payload.item_id, region)
+        .await?;
 
-        let materia_count: i32 = listing.materia.len().try_into()?;
-        rows_affected += sqlx::query!(
-            "INSERT INTO listing (
-                upload_id, world_id, item_id, seller_id,
-                retainer_id, retainer_name, creator_id, creator_name,
-                last_review_time, price_per_unit, quantity,
-                retainer_city_id, materia_count, hq)
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14)
-            ",
-            id,
-            payload.world_id,
-            payload.item_id,
-            listing.seller_id,
-            listing.retainer_id,
-            listing.retainer_name,
-            listing.creator_id,
-            listing.creator_name,
-            date,
-            listing.price_per_unit,
-            listing.quantity,
-            listing.retainer_city,
-            materia_count,
-            listing.hq,
-        )
-        .execute(&state.pool)
-        .await?
-        .rows_affected();
-    }
+    sqlx::query!(
+        "INSERT INTO listing_history (
+            upload_id, upload_time, world_id, item_id, seller_id,
+            retainer_id, retainer_name, creator_id, creator_name,
+            last_review_time, price_per_unit, quantity,
+            retainer_city_id, materia_count, hq, listing_index, region)
+        SELECT
+            upload_id, $2, world_id, item_id, seller_id,
+            retainer_id, retainer_name, creator_id, creator_name,
+            last_review_time, price_per_unit, quantity,
+            retainer_city_id, materia_count, hq, listing_index, region
+        FROM listing WHERE upload_id = $1",
+        id,
+        date
+    )
+    .execute(&mut trans)
+    .await?;
+
+    trans.commit().await?;
 
     let upload_time_elapsed = upload_time.elapsed();
 
     increment_counter!("xivhub_update", "type" => "listings");
     histogram!("xivhub_update_time", upload_time_elapsed, "type" => "listings");
+    histogram!("xivhub_batch_rows", f64::from(batch.len()), "type" => "listings");
+    histogram!("xivhub_batch_rows", f64::from(batch.materia_len()), "type" => "listing_materia");
 
     if rows_affected > 0 {
-        state.item_listings_cache.invalidate(&payload.item_id).await;
+        let item_id = payload.item_id;
+        state
+            .item_listings_cache
+            .invalidate_entries_if(move |key, _| key.0 == item_id)
+            .map_err(|e| AppError::Internal(e.into()))?;
     }
 
     Ok(())
@@ -149,73 +325,82 @@ pub async fn history(
         payload.item_id
     );
 
-    if payload.world_id > 1000 || payload.world_id == 0 {
-        // mostly chinese servers
-        // todo: handle better
-        return Ok(());
-    }
+    let region = validate_upload(&payload, &state.pool).await?;
 
     let mut trans = state.pool.begin().await?;
 
     sqlx::query!(
-        "INSERT INTO upload (id, uploader_id, upload_time, world_id, item_id, upload_type)
-        VALUES ($1,$2,$3,$4,$5,$6)",
+        "INSERT INTO upload (id, uploader_id, upload_time, world_id, item_id, upload_type, region)
+        VALUES ($1,$2,$3,$4,$5,$6,$7)",
         id,
         payload.uploader_id,
         date,
         payload.world_id,
         payload.item_id,
-        1
+        1,
+        region.as_str()
     )
     .execute(&mut trans)
     .await?;
 
-    let mut rows_affected = 0;
-
-    // If iter is not emtpy returns some.
-    if let Some(oldest_purchase) = payload.listings.iter().map(|x| x.purchase_time).min() {
-        let oldest_date = chrono::Utc
-            .timestamp_opt(oldest_purchase, 0)
+    // the days before it are only kept in the rollups, which are no longer rebuilt for them.
+    let purged_before =
+        sqlx::query_scalar!("SELECT purged_before FROM rollup_state WHERE name = 'daily'")
+            .fetch_one(&mut trans)
+            .await?;
+
+    let total = payload.listings.len();
+    let mut expired_rows: u64 = 0;
+    let mut buyer_names = Vec::with_capacity(total);
+    let mut hqs = Vec::with_capacity(total);
+    let mut on_mannequins = Vec::with_capacity(total);
+    let mut purchase_times = Vec::with_capacity(total);
+    let mut quantities = Vec::with_capacity(total);
+    let mut prices = Vec::with_capacity(total);
+    for listing in payload.listings {
+        let date = chrono::Utc
+            .timestamp_opt(listing.purchase_time, 0)
             .single()
-            .ok_or_else(|| AppError(eyre!("invalid oldest purchase date")))?;
-
-        // delete records more recent than the last purchase time
-        rows_affected = sqlx::query!(
-            "DELETE FROM purchase WHERE item_id = $1 AND world_id = $2 AND purchase_time >= $3",
-            payload.item_id,
-            payload.world_id,
-            oldest_date
-        )
-        .execute(&mut trans)
-        .await?
-        .rows_affected();
-
-        for listing in payload.listings {
-            let date = chrono::Utc
-                .timestamp_opt(listing.purchase_time, 0)
-                .single()
-                .ok_or_else(|| AppError(eyre!("invalid purchase_time")))?;
-
-            rows_affected = sqlx::query!(
-                "INSERT INTO purchase (
-                    upload_id, item_id, world_id, buyer_name, hq, on_mannequin, purchase_time, quantity, price_per_unit)
-                VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
-                ",
-                id,
-                payload.item_id,
-                payload.world_id,
-                listing.buyer_name,
-                listing.hq,
-                listing.on_mannequin,
-                date,
-                listing.quantity,
-                listing.price_per_unit
-            )
-            .execute(&mut trans)
-            .await?.rows_affected();
+            .ok_or_else(|| AppError::BadRequest("invalid purchase_time".to_string()))?;
+        if date.date_naive() < purged_before {
+            expired_rows += 1;
+            continue;
         }
+
+        buyer_names.push(listing.buyer_name);
+        hqs.push(listing.hq);
+        on_mannequins.push(listing.on_mannequin);
+        purchase_times.push(date);
+        quantities.push(listing.quantity);
+        prices.push(listing.price_per_unit);
     }
 
+    // uploads overlap, the purchases already known are skipped by the natural key.
+    let new_rows = sqlx::query!(
+        "INSERT INTO purchase (
+            upload_id, item_id, world_id, buyer_name, hq, on_mannequin, purchase_time, quantity, price_per_unit, region)
+        SELECT $1, $2, $3, buyer_name, hq, on_mannequin, purchase_time, quantity, price_per_unit, $10
+        FROM UNNEST($4::TEXT[], $5::BOOL[], $6::BOOL[], $7::TIMESTAMPTZ[], $8::INT[], $9::INT[])
+            AS p(buyer_name, hq, on_mannequin, purchase_time, quantity, price_per_unit)
+        ON CONFLICT (item_id, world_id, buyer_name, purchase_time, price_per_unit, quantity, hq) DO NOTHING",
+        id,
+        payload.item_id,
+        payload.world_id,
+        &buyer_names,
+        &hqs,
+        &on_mannequins,
+        &purchase_times,
+        &quantities,
+        &prices,
+        region.as_str()
+    )
+    .execute(&mut trans)
+    .await?
+    .rows_affected();
+    let duplicate_rows = u64::try_from(total)
+        .map_err(|e| AppError::Internal(e.into()))?
+        .saturating_sub(new_rows + expired_rows);
+
     let upload_time = Instant::now();
 
     trans.commit().await?;
@@ -224,18 +409,40 @@ pub async fn history(
 
     increment_counter!("xivhub_update", "type" => "history");
     histogram!("xivhub_query", upload_time_elapsed, "type" => "history");
+    counter!("xivhub_purchase_rows", new_rows, "type" => "new");
+    counter!("xivhub_purchase_rows", duplicate_rows, "type" => "duplicate");
+    counter!("xivhub_purchase_rows", expired_rows, "type" => "expired");
+    info!(
+        "Purchase history upload {id} for item {}: {new_rows} new, {duplicate_rows} duplicate, {expired_rows} expired",
+        payload.item_id
+    );
 
-    if rows_affected > 0 {
-        state.item_purchase_cache.invalidate(&payload.item_id).await;
+    if new_rows > 0 {
+        let item_id = payload.item_id;
+        state
+            .item_purchase_cache
+            .invalidate_entries_if(move |key, _| key.0 == item_id)
+            .map_err(|e| AppError::Internal(e.into()))?;
     }
     Ok(())
 }
 
-pub async fn last_uploads(State(state): State<AppState>) -> Result<Json<Vec<Upload>>, AppError> {
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct LastUploadsQuery {
+    pub region: Option<Region>,
+}
+
+pub async fn last_uploads(
+    State(state): State<AppState>,
+    Query(query): Query<LastUploadsQuery>,
+) -> Result<Json<Vec<Upload>>, AppError> {
+    let region = query.region.unwrap_or_default();
+
     let start = Instant::now();
     let uploads = sqlx::query_as!(
         Upload,
-        "SELECT u.*, f.name, f.icon FROM upload u LEFT JOIN item_info f ON f.item_id = u.item_id WHERE upload_type = 0 ORDER BY upload_time DESC LIMIT 250"
+        "SELECT u.*, f.name, f.icon FROM upload u LEFT JOIN item_info f ON f.item_id = u.item_id WHERE upload_type = 0 AND region = $1 ORDER BY upload_time DESC LIMIT 250",
+        region.as_str()
     )
     .fetch_all(&state.pool)
     .await?;
@@ -0,0 +1,104 @@
+use crate::{error::AppError, extractor::Query, language::Language, world::Region, AppState};
+use axum::{extract::State, Json};
+use axum_prometheus::metrics::histogram;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VendorFlipsQuery {
+    /// Only use listings of this world.
+    pub world: Option<i32>,
+    pub region: Option<Region>,
+    #[validate(range(min = 1, max = 500))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Flip {
+    /// Buy the cheapest listing and sell it to a vendor.
+    SellToVendor,
+    /// Buy from a gil shop and undercut the cheapest listing.
+    BuyFromVendor,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VendorFlip {
+    pub item_id: i32,
+    pub name: String,
+    pub icon: String,
+    pub flip: Flip,
+    /// The cheapest listing in scope.
+    pub price_per_unit: i32,
+    pub world_id: i32,
+    pub price_mid: i32,
+    pub price_low: i32,
+    pub profit_per_unit: i32,
+}
+
+/// returns items whose cheapest listing is below the vendor sell price or above the vendor buy price,
+/// most profitable first
+pub async fn vendor_flips(
+    State(state): State<AppState>,
+    Query(query): Query<VendorFlipsQuery>,
+    lang: Language,
+) -> Result<Json<Vec<VendorFlip>>, AppError> {
+    query.validate()?;
+    let region = Region::resolve(query.region, query.world)?;
+
+    let start = Instant::now();
+    let rows = sqlx::query!(
+        r#"WITH cheapest AS (
+            SELECT DISTINCT ON (item_id) item_id, price_per_unit, world_id FROM listing
+            WHERE region = $1 AND ($2::INT IS NULL OR world_id = $2)
+            ORDER BY item_id, price_per_unit ASC
+        )
+        SELECT f.item_id as "item_id!", f.name as "name!", f.icon as "icon!",
+        f.price_per_unit as "price_per_unit!", f.world_id as "world_id!", f.price_mid as "price_mid!",
+        f.price_low as "price_low!", f.profit_per_unit as "profit_per_unit!"
+        FROM (
+            SELECT c.item_id, COALESCE(t.name, i.name) as name, i.icon, c.price_per_unit, c.world_id,
+            i.price_mid, i.price_low,
+            GREATEST(
+                i.price_low - c.price_per_unit,
+                CASE WHEN i.sold_by_vendor THEN c.price_per_unit - i.price_mid ELSE 0 END
+            ) as profit_per_unit
+            FROM cheapest c
+            JOIN item_info i ON i.item_id = c.item_id
+            LEFT JOIN item_info_locale t ON t.item_id = c.item_id AND t.lang = $3
+        ) f
+        WHERE f.profit_per_unit > 0
+        ORDER BY f.profit_per_unit DESC, f.item_id
+        LIMIT $4"#,
+        region.as_str(),
+        query.world,
+        lang.as_str(),
+        query.limit.unwrap_or(100)
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "vendor_flips");
+
+    let flips = rows
+        .into_iter()
+        .map(|x| VendorFlip {
+            item_id: x.item_id,
+            name: x.name,
+            icon: x.icon,
+            flip: if x.price_low - x.price_per_unit == x.profit_per_unit {
+                Flip::SellToVendor
+            } else {
+                Flip::BuyFromVendor
+            },
+            price_per_unit: x.price_per_unit,
+            world_id: x.world_id,
+            price_mid: x.price_mid,
+            price_low: x.price_low,
+            profit_per_unit: x.profit_per_unit,
+        })
+        .collect();
+
+    Ok(Json(flips))
+}
@@ -0,0 +1,106 @@
+use crate::{
+    entities::DataCenter,
+    error::AppError,
+    world::{game_region_name, Region},
+    AppState,
+};
+use axum::{extract::State, Json};
+use axum_prometheus::metrics::histogram;
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Debug, Serialize)]
+pub struct WorldInfo {
+    pub world_id: i32,
+    pub name: String,
+    pub data_center_id: i32,
+    pub data_center_name: String,
+    pub region_id: i32,
+}
+
+pub async fn worlds(State(state): State<AppState>) -> Result<Json<Vec<WorldInfo>>, AppError> {
+    let start = Instant::now();
+    let worlds = sqlx::query_as!(
+        WorldInfo,
+        "SELECT w.world_id, w.name, w.data_center_id, d.name as data_center_name, d.region_id
+        FROM world w JOIN data_center d ON d.data_center_id = w.data_center_id
+        ORDER BY w.world_id ASC"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "worlds");
+
+    Ok(Json(worlds))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataCenterInfo {
+    pub data_center_id: i32,
+    pub name: String,
+    pub region_id: i32,
+    pub worlds: Vec<i32>,
+}
+
+pub async fn data_centers(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DataCenterInfo>>, AppError> {
+    let start = Instant::now();
+    let data_centers = sqlx::query_as!(
+        DataCenterInfo,
+        r#"SELECT d.data_center_id, d.name, d.region_id,
+        ARRAY(SELECT w.world_id FROM world w WHERE w.data_center_id = d.data_center_id ORDER BY w.world_id) as "worlds!"
+        FROM data_center d
+        ORDER BY d.data_center_id ASC"#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "data_centers");
+
+    Ok(Json(data_centers))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegionInfo {
+    pub region_id: i32,
+    pub name: &'static str,
+    /// The market (game client) the region trades in.
+    pub market: Region,
+    pub data_centers: Vec<i32>,
+}
+
+pub async fn regions(State(state): State<AppState>) -> Result<Json<Vec<RegionInfo>>, AppError> {
+    let start = Instant::now();
+    let data_centers = sqlx::query_as!(
+        DataCenter,
+        "SELECT * FROM data_center ORDER BY data_center_id ASC"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let elapsed = start.elapsed();
+    histogram!("xivhub_query", elapsed, "type" => "regions");
+
+    let mut regions: Vec<RegionInfo> = Vec::new();
+    for dc in data_centers {
+        let (Some(name), Some(market)) = (
+            game_region_name(dc.region_id),
+            Region::of_game_region(dc.region_id),
+        ) else {
+            continue;
+        };
+
+        match regions.iter_mut().find(|r| r.region_id == dc.region_id) {
+            Some(region) => region.data_centers.push(dc.data_center_id),
+            None => regions.push(RegionInfo {
+                region_id: dc.region_id,
+                name,
+                market,
+                data_centers: vec![dc.data_center_id],
+            }),
+        }
+    }
+    regions.sort_by_key(|r| r.region_id);
+
+    Ok(Json(regions))
+}
@@ -1,9 +1,84 @@
 use sqlx::PgPool;
 
-use crate::entities::ItemInfo;
+use crate::{entities::ItemInfo, language::Language};
 
-pub async fn fetch_item_info(id: i32, db: &PgPool) -> Result<ItemInfo, sqlx::Error> {
-    sqlx::query_as!(ItemInfo, "SELECT * from item_info where item_id = $1", id)
-        .fetch_one(db)
-        .await
+/// Returns the item with its texts in `lang`, english when they were not imported.
+pub async fn fetch_item_info(
+    id: i32,
+    lang: Language,
+    db: &PgPool,
+) -> Result<ItemInfo, sqlx::Error> {
+    sqlx::query_as!(
+        ItemInfo,
+        r#"SELECT i.item_id, COALESCE(t.name, i.name) as "name!", i.icon, i.icon_hd,
+        COALESCE(t.description, i.description) as "description!", i.item_kind_name, i.item_kind_id,
+        i.item_search_category, i.item_search_category_iconhd,
+        COALESCE(t.item_search_category_name, i.item_search_category_name) as "item_search_category_name!",
+        i.stack_size, i.level_item, i.level_equip, i.materia_slot_count, i.rarity, i.can_be_hq,
+        i.price_mid, i.price_low, i.sold_by_vendor
+        FROM item_info i
+        LEFT JOIN item_info_locale t ON t.item_id = i.item_id AND t.lang = $2
+        WHERE i.item_id = $1"#,
+        id,
+        lang.as_str()
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Escapes the wildcards of user input matched with `LIKE ... ESCAPE '\'`.
+#[must_use]
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Parses a duration like `12h`, `7d` or `2w`, None when it is too long to be represented.
+#[must_use]
+pub fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let (split, _) = value.char_indices().last()?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().ok().filter(|x| *x > 0)?;
+
+    let unit_seconds = match unit {
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    // chrono's constructors panic on overflow, `from_std` returns an error instead.
+    let seconds = amount.checked_mul(unit_seconds)?;
+    chrono::Duration::from_std(std::time::Duration::from_secs(seconds)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_like, parse_duration};
+
+    #[test]
+    fn like_patterns() {
+        assert_eq!(escape_like("Iron Ore"), "Iron Ore");
+        assert_eq!(escape_like("100%_a\\b"), "100\\%\\_a\\\\b");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("7d"), Some(chrono::Duration::days(7)));
+        assert_eq!(parse_duration("12h"), Some(chrono::Duration::hours(12)));
+        assert_eq!(parse_duration("0d"), None);
+        assert_eq!(parse_duration("d"), None);
+        assert_eq!(parse_duration("7m"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("7é"), None);
+        assert_eq!(parse_duration("-7d"), None);
+        assert_eq!(parse_duration("99999999999999999w"), None);
+        assert_eq!(parse_duration("18446744073709551615h"), None);
+    }
 }
@@ -0,0 +1,197 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+/// The game clients, each one has its own set of worlds and its own market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    /// Japan, North America, Europe and Oceania.
+    #[default]
+    Global,
+    #[serde(rename = "cn")]
+    China,
+    #[serde(rename = "kr")]
+    Korea,
+}
+
+impl Region {
+    /// Returns the region of a world id, worlds of each client use their own id range.
+    /// The range does not mean the world exists, the `world` table has the actual worlds.
+    #[must_use]
+    pub const fn of_world(world_id: i32) -> Option<Self> {
+        match world_id {
+            1..=999 => Some(Self::Global),
+            1000..=1999 => Some(Self::China),
+            2000..=2999 => Some(Self::Korea),
+            _ => None,
+        }
+    }
+
+    /// Returns the market a region id from the `WorldDCGroupType` sheet belongs to.
+    #[must_use]
+    pub const fn of_game_region(region_id: i32) -> Option<Self> {
+        match region_id {
+            1..=4 => Some(Self::Global),
+            5 => Some(Self::China),
+            6 => Some(Self::Korea),
+            _ => None,
+        }
+    }
+
+    /// The region a query with an optional `region` and `world` looks at:
+    /// `region`, else the region of `world`, else global.
+    /// A world outside of `region` is rejected, the query would match nothing.
+    pub fn resolve(region: Option<Self>, world_id: Option<i32>) -> Result<Self, AppError> {
+        let Some(world_id) = world_id else {
+            return Ok(region.unwrap_or_default());
+        };
+
+        match (region, Self::of_world(world_id)) {
+            (_, None) => Err(AppError::BadRequest(format!("unknown world {world_id}"))),
+            (Some(region), Some(of_world)) if region != of_world => Err(AppError::BadRequest(
+                format!("world {world_id} is not in region {}", region.as_str()),
+            )),
+            (_, Some(of_world)) => Ok(of_world),
+        }
+    }
+
+    /// The value stored in the `region` columns.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::China => "cn",
+            Self::Korea => "kr",
+        }
+    }
+}
+
+/// Name of a region id from the `WorldDCGroupType` sheet.
+#[must_use]
+pub const fn game_region_name(region_id: i32) -> Option<&'static str> {
+    match region_id {
+        1 => Some("Japan"),
+        2 => Some("North America"),
+        3 => Some("Europe"),
+        4 => Some("Oceania"),
+        5 => Some("China"),
+        6 => Some("Korea"),
+        _ => None,
+    }
+}
+
+/// Finds a game region by id or by name, e.g. `3`, `europe` or `north-america`.
+#[must_use]
+pub fn game_region_by_name(name: &str) -> Option<i32> {
+    if let Ok(id) = name.parse() {
+        return game_region_name(id).map(|_| id);
+    }
+
+    (1..=6).find(|&id| {
+        matches!(game_region_name(id), Some(region) if region.replace(' ', "-").eq_ignore_ascii_case(name))
+    })
+}
+
+/// The part of the market a query looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Every world of a game client.
+    Market(Region),
+    /// A game region id, see [`game_region_name`].
+    Region(i32),
+    DataCenter(i32),
+    World(i32),
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::Market(Region::Global)
+    }
+}
+
+impl Scope {
+    /// Parses the `region` query parameter, either a market (`global`, `cn`, `kr`) or a game region.
+    #[must_use]
+    pub fn parse_region(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "global" => Some(Self::Market(Region::Global)),
+            "cn" => Some(Self::Market(Region::China)),
+            "kr" => Some(Self::Market(Region::Korea)),
+            _ => game_region_by_name(value).map(Self::Region),
+        }
+    }
+
+    #[must_use]
+    pub const fn market(self) -> Option<Region> {
+        match self {
+            Self::Market(region) => Some(region),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn game_region(self) -> Option<i32> {
+        match self {
+            Self::Region(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn data_center(self) -> Option<i32> {
+        match self {
+            Self::DataCenter(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn world(self) -> Option<i32> {
+        match self {
+            Self::World(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Region, Scope};
+
+    #[test]
+    fn region_of_world() {
+        assert_eq!(Region::of_world(40), Some(Region::Global));
+        assert_eq!(Region::of_world(1042), Some(Region::China));
+        assert_eq!(Region::of_world(2075), Some(Region::Korea));
+        assert_eq!(Region::of_world(0), None);
+        assert_eq!(Region::of_world(5000), None);
+    }
+
+    #[test]
+    fn resolve_region() {
+        assert_eq!(Region::resolve(None, None).ok(), Some(Region::Global));
+        assert_eq!(
+            Region::resolve(Some(Region::Korea), None).ok(),
+            Some(Region::Korea)
+        );
+        assert_eq!(Region::resolve(None, Some(1042)).ok(), Some(Region::China));
+        assert_eq!(
+            Region::resolve(Some(Region::China), Some(1042)).ok(),
+            Some(Region::China)
+        );
+        assert!(Region::resolve(Some(Region::Global), Some(1042)).is_err());
+        assert!(Region::resolve(None, Some(5000)).is_err());
+    }
+
+    #[test]
+    fn parse_region_scope() {
+        assert_eq!(
+            Scope::parse_region("cn"),
+            Some(Scope::Market(Region::China))
+        );
+        assert_eq!(Scope::parse_region("Europe"), Some(Scope::Region(3)));
+        assert_eq!(Scope::parse_region("north-america"), Some(Scope::Region(2)));
+        assert_eq!(Scope::parse_region("4"), Some(Scope::Region(4)));
+        assert_eq!(Scope::parse_region("mars"), None);
+    }
+}